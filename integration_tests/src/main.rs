@@ -1,9 +1,19 @@
+mod mock_issuer;
+
 use std::{process::Command, io::{stdout, Write}};
 
 use futures_util::FutureExt;
 use qa_api::{config, oneshot};
 use reqwest::Client;
 use serde::{Serialize, Deserialize};
+use sqlx::{Connection, PgConnection};
+
+// a key from before the last rotation, tokens it encrypted are still accepted
+const RETIRED_PASETO_KEY_ID: &str = "retired";
+const RETIRED_PASETO_KEY: &str = "OLDER GRUMPY HARBOR DUKE DEGREE!";
+// the ed25519 key pair of RFC 8032's first test vector
+const SIGNING_SEED: &str = "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60";
+const SIGNING_PUBLIC_KEY: &str = "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a";
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 struct UserDTO {
@@ -36,16 +46,41 @@ struct AnswerDTO {
 struct Answer {
     id: i32,
     content: String,
-    question_id: i32
+    question_id: i32,
+    is_accepted: bool
 }
 
 #[derive(Debug, Deserialize, Clone)]
 struct Token(String);
 
+#[derive(Debug, Deserialize, Clone)]
+struct TokenPair {
+    access_token: String,
+    refresh_token: String,
+}
+
+#[derive(Debug, Serialize)]
+struct PasswordChange {
+    old_password: String,
+    new_password: String,
+}
+
+#[derive(Debug, Serialize)]
+struct RefreshRequest {
+    refresh_token: String,
+}
+
 #[tokio::main]
 async fn main() -> Result<(), warp::Rejection> {
     // need to call the env variables
     dotenv::dotenv().ok();
+    // oidc logins go to the mock issuer started below
+    std::env::set_var("OIDC_ISSUER", mock_issuer::ISSUER);
+    // low enough for a few votes to unlock editing other people's posts
+    std::env::set_var("REPUTATION_TO_EDIT", "30");
+    // every step logs in from the same address, and some fail on purpose
+    std::env::set_var("LOGIN_MAX_ATTEMPTS", "10");
+    std::env::set_var("PASETO_RETIRED_KEYS", format!("{}:{}", RETIRED_PASETO_KEY_ID, RETIRED_PASETO_KEY));
     // set the configuration
     let config = config::Config::new().expect("Config can't be set");
 
@@ -74,11 +109,22 @@ async fn main() -> Result<(), warp::Rejection> {
 
     let repository = qa_api::setup_repository(&config).await?;
 
-    let handler = oneshot(repository).await;
+    let handler = oneshot(&config, repository.clone()).await;
+    // a second server on its own port, signing its session tokens
+    std::env::set_var("PORT", "8081");
+    std::env::set_var("PASETO_MODE", "public");
+    std::env::set_var("PASETO_SIGNING_KEY", SIGNING_SEED);
+    let public_config = config::Config::new().expect("Config can't be set");
+    let public_handler = oneshot(&public_config, repository).await;
+    std::env::set_var("PORT", config.api_port.to_string());
+    std::env::set_var("PASETO_MODE", "local");
+    std::env::remove_var("PASETO_SIGNING_KEY");
+    mock_issuer::spawn();
 
     let token: Token;
+    let mut tokens: TokenPair;
     let question_returned: Question;
-    let _answer_returned: Answer;
+    let answer_returned: Answer;
 
     // create a test user to use throughout the tests
     let user = UserDTO {
@@ -102,7 +148,22 @@ async fn main() -> Result<(), warp::Rejection> {
 
     match result {
         Ok(t) => {
-            token = t;
+            tokens = t;
+            println!(" ✓");
+        },
+        Err(_) => {
+            let _ = handler.sender.send(1);
+            std::process::exit(1);
+        }
+    }
+
+    print!("Running refresh token!!!");
+    let result = std::panic::AssertUnwindSafe(refresh_tokens(&tokens, &user)).catch_unwind().await;
+
+    match result {
+        Ok(t) => {
+            tokens = t;
+            token = Token(tokens.access_token.clone());
             println!(" ✓");
         },
         Err(_) => {
@@ -145,7 +206,7 @@ async fn main() -> Result<(), warp::Rejection> {
 
     match result {
         Ok(a) => {
-            _answer_returned = a;
+            answer_returned = a;
             println!(" ✓")
         },
         Err(_) => {
@@ -154,11 +215,230 @@ async fn main() -> Result<(), warp::Rejection> {
         }
     }
 
+    print!("Running list answers!!!");
+    let result = std::panic::AssertUnwindSafe(list_answers(&token, &answer_returned)).catch_unwind().await;
+
+    match result {
+        Ok(_) => println!(" ✓"),
+        Err(_) => {
+            let _ = handler.sender.send(1);
+            std::process::exit(1);
+        }
+    }
+
+    print!("Running question with answers!!!");
+    let result = std::panic::AssertUnwindSafe(get_question_with_answers(&answer_returned)).catch_unwind().await;
+
+    match result {
+        Ok(_) => println!(" ✓"),
+        Err(_) => {
+            let _ = handler.sender.send(1);
+            std::process::exit(1);
+        }
+    }
+
+    print!("Running accept answer!!!");
+    let result = std::panic::AssertUnwindSafe(accept_answer(&token, &answer_returned)).catch_unwind().await;
+
+    match result {
+        Ok(_) => println!(" ✓"),
+        Err(_) => {
+            let _ = handler.sender.send(1);
+            std::process::exit(1);
+        }
+    }
+
+    print!("Running votes!!!");
+    let result = std::panic::AssertUnwindSafe(vote(&token, &answer_returned)).catch_unwind().await;
+
+    match result {
+        Ok(_) => println!(" ✓"),
+        Err(_) => {
+            let _ = handler.sender.send(1);
+            std::process::exit(1);
+        }
+    }
+
+    print!("Running reputation!!!");
+    let result = std::panic::AssertUnwindSafe(reputation(&token, &answer_returned)).catch_unwind().await;
+
+    match result {
+        Ok(_) => println!(" ✓"),
+        Err(_) => {
+            let _ = handler.sender.send(1);
+            std::process::exit(1);
+        }
+    }
+
+    print!("Running comments!!!");
+    let result = std::panic::AssertUnwindSafe(comments(&token, &answer_returned)).catch_unwind().await;
+
+    match result {
+        Ok(_) => println!(" ✓"),
+        Err(_) => {
+            let _ = handler.sender.send(1);
+            std::process::exit(1);
+        }
+    }
+
+    print!("Running delete answer and question!!!");
+    let result = std::panic::AssertUnwindSafe(delete_answer_and_question(&token, &question)).catch_unwind().await;
+
+    match result {
+        Ok(_) => println!(" ✓"),
+        Err(_) => {
+            let _ = handler.sender.send(1);
+            std::process::exit(1);
+        }
+    }
+
+    print!("Running update profile!!!");
+    let result = std::panic::AssertUnwindSafe(update_profile(&token)).catch_unwind().await;
+
+    match result {
+        Ok(_) => println!(" ✓"),
+        Err(_) => {
+            let _ = handler.sender.send(1);
+            std::process::exit(1);
+        }
+    }
+
+    print!("Running change password!!!");
+    let result = std::panic::AssertUnwindSafe(change_password(&token, &user)).catch_unwind().await;
+
+    match result {
+        Ok(_) => println!(" ✓"),
+        Err(_) => {
+            let _ = handler.sender.send(1);
+            std::process::exit(1);
+        }
+    }
+
+    print!("Running list accounts as a regular user!!!");
+    let result = std::panic::AssertUnwindSafe(list_accounts_forbidden(&token)).catch_unwind().await;
+
+    match result {
+        Ok(_) => println!(" ✓"),
+        Err(_) => {
+            let _ = handler.sender.send(1);
+            std::process::exit(1);
+        }
+    }
+
+    print!("Running api keys!!!");
+    let result = std::panic::AssertUnwindSafe(use_api_key(&token, &question)).catch_unwind().await;
+
+    match result {
+        Ok(_) => println!(" ✓"),
+        Err(_) => {
+            let _ = handler.sender.send(1);
+            std::process::exit(1);
+        }
+    }
+
+    print!("Running scoped token!!!");
+    let result = std::panic::AssertUnwindSafe(use_scoped_token(&token, &question)).catch_unwind().await;
+
+    match result {
+        Ok(_) => println!(" ✓"),
+        Err(_) => {
+            let _ = handler.sender.send(1);
+            std::process::exit(1);
+        }
+    }
+
+    print!("Running key rotation!!!");
+    let result = std::panic::AssertUnwindSafe(rotate_keys(&token, &config)).catch_unwind().await;
+
+    match result {
+        Ok(_) => println!(" ✓"),
+        Err(_) => {
+            let _ = handler.sender.send(1);
+            std::process::exit(1);
+        }
+    }
+
+    print!("Running public tokens!!!");
+    let result = std::panic::AssertUnwindSafe(use_public_tokens()).catch_unwind().await;
+
+    match result {
+        Ok(_) => println!(" ✓"),
+        Err(_) => {
+            let _ = handler.sender.send(1);
+            std::process::exit(1);
+        }
+    }
+
+    print!("Running oidc login!!!");
+    let result = std::panic::AssertUnwindSafe(login_with_oidc()).catch_unwind().await;
+
+    match result {
+        Ok(_) => println!(" ✓"),
+        Err(_) => {
+            let _ = handler.sender.send(1);
+            std::process::exit(1);
+        }
+    }
+
+    print!("Running sessions!!!");
+    let result = std::panic::AssertUnwindSafe(sign_out_device(&token, &user)).catch_unwind().await;
+
+    match result {
+        Ok(_) => println!(" ✓"),
+        Err(_) => {
+            let _ = handler.sender.send(1);
+            std::process::exit(1);
+        }
+    }
+
+    print!("Running two-factor login!!!");
+    let result = std::panic::AssertUnwindSafe(login_with_two_factor()).catch_unwind().await;
+
+    match result {
+        Ok(_) => println!(" ✓"),
+        Err(_) => {
+            let _ = handler.sender.send(1);
+            std::process::exit(1);
+        }
+    }
+
+    print!("Running delete account!!!");
+    let result = std::panic::AssertUnwindSafe(delete_account(&question)).catch_unwind().await;
+
+    match result {
+        Ok(_) => println!(" ✓"),
+        Err(_) => {
+            let _ = handler.sender.send(1);
+            std::process::exit(1);
+        }
+    }
+
+    print!("Running logout!!!");
+    let result = std::panic::AssertUnwindSafe(logout_user(&token, &question)).catch_unwind().await;
+
+    match result {
+        Ok(_) => println!(" ✓"),
+        Err(_) => {
+            let _ = handler.sender.send(1);
+            std::process::exit(1);
+        }
+    }
+
+    let _ = public_handler.sender.send(0);
     Ok(())
 }
 
 async fn register_new_user(user: &UserDTO) {
     let client = reqwest::Client::new();
+    let invalid = UserDTO { email: "not an email".to_string(), password: user.password.clone() };
+    let res = client.post("http://localhost:8080/registration")
+        .json(&invalid)
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), 400);
+
     let res = client.post("http://localhost:8080/registration")
         .json(&user)
         .send()
@@ -168,10 +448,30 @@ async fn register_new_user(user: &UserDTO) {
         .await;
 
     assert_eq!(res.unwrap(), "Account added".to_string());
+
+    // a new verification mail, answered the same for unknown emails
+    for email in [user.email.as_str(), "unknown@email.com"] {
+        let res = client.post("http://localhost:8080/registration/resend")
+            .json(&serde_json::json!({ "email": email }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 200);
+    }
 }
 
-async fn login_user(user: &UserDTO) -> Token {
+async fn login_user(user: &UserDTO) -> TokenPair {
     let client = reqwest::Client::new();
+    // an unknown email answers exactly like a wrong password
+    let unknown = UserDTO { email: "unknown@email.com".to_string(), password: user.password.clone() };
+    let res = client.post("http://localhost:8080/login")
+        .json(&unknown)
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), 401);
+
     let res = client.post("http://localhost:8080/login")
         .json(&user)
         .send()
@@ -180,49 +480,1221 @@ async fn login_user(user: &UserDTO) -> Token {
 
     assert_eq!(res.status(), 200);
 
-    res.json::<Token>()
+    res.json::<TokenPair>()
         .await
         .unwrap()
 }
 
-async fn create_question(token: &Token, question: &QuestionDTO) -> Question {
-    let res: Question = post_entity(token, &question, "http://localhost:8080/question").await;
-    assert_eq!(res.id, 1);
-    assert_eq!(res.content, question.content);
+async fn refresh_tokens(tokens: &TokenPair, user: &UserDTO) -> TokenPair {
+    let client = reqwest::Client::new();
+    let request = RefreshRequest { refresh_token: tokens.refresh_token.clone() };
 
-    res
-}
+    let res = client.post("http://localhost:8080/token/refresh")
+        .json(&request)
+        .send()
+        .await
+        .unwrap();
 
-async fn create_answer(token: &Token, answer: &AnswerDTO) -> Answer {
-    let res: Answer = post_entity(token, &answer, "http://localhost:8080/answer").await;
-    assert_eq!(res.id, 1);
-    assert_eq!(res.content, answer.content);
-    assert_eq!(res.question_id, answer.question_id);
+    assert_eq!(res.status(), 200);
 
-    res
-}
+    let refreshed = res.json::<TokenPair>()
+        .await
+        .unwrap();
+    assert_ne!(refreshed.refresh_token, tokens.refresh_token);
 
-// below, we use Higher-Ranked Trait Bounds (HRTB) for S generic types, but why?
-// This is used when the trait implementation must be valid for any lifetime 'de,
-// not just a specific one. In this case, it means that S must implement the 
-// Deserialize trait for any lifetime, not just a specific lifetime 'a.
-// When S: for<'de> Deserialize<'de> is used, it means S must be a type that can 
-// be deserialized from any possible lifetime 'de. This is a requirement for the
-// json method from the reqwest library, because it doesn't know the exact 
-// lifetime of the data it will be receiving from the network, so it requires a 
-// type that can handle any possible lifetime.
-async fn post_entity<'a, T, S>(token: &'a Token, entity: &'a T, action_url: &'a str) -> S
-    where T: Serialize + 'a, S: for<'b> Deserialize<'b> {
-    let client = Client::new();
-    let response = client.post(action_url)
-        .header("Authorization", token.0.clone())
-        .json(&entity)
+    // the first refresh token was rotated out and must not be accepted again
+    let res = client.post("http://localhost:8080/token/refresh")
+        .json(&request)
         .send()
         .await
-        .unwrap()
-        .json::<S>()
+        .unwrap();
+
+    assert_eq!(res.status(), 401);
+
+    // the reuse signed the whole session out, refreshed tokens included
+    let res = client.get("http://localhost:8080/account/me")
+        .header("Authorization", refreshed.access_token)
+        .send()
         .await
         .unwrap();
 
-    response
-}
\ No newline at end of file
+    assert_eq!(res.status(), 401);
+
+    login_user(user).await
+}
+
+async fn list_answers(token: &Token, first: &Answer) {
+    let client = reqwest::Client::new();
+    let second = AnswerDTO { content: "Another answer".to_string(), question_id: first.question_id };
+    let second: Answer = post_entity(token, &second, "http://localhost:8080/answer").await;
+
+    let url = format!("http://localhost:8080/question/{}/answers", first.question_id);
+    let newest = client.get(&url).send().await.unwrap().json::<Vec<Answer>>().await.unwrap();
+    assert_eq!(newest.iter().map(|a| a.id).collect::<Vec<_>>(), vec![second.id, first.id]);
+
+    let oldest = client.get(format!("{}?sort=oldest&limit=1&offset=0", url))
+        .send()
+        .await
+        .unwrap()
+        .json::<Vec<Answer>>()
+        .await
+        .unwrap();
+    assert_eq!(oldest.len(), 1);
+    assert_eq!(oldest[0].id, first.id);
+    assert_eq!(oldest[0].content, first.content);
+
+    let res = client.get(format!("{}?sort=random", url)).send().await.unwrap();
+    assert_eq!(res.status(), 400);
+
+    let res = client.get("http://localhost:8080/question/9999/answers").send().await.unwrap();
+    assert_eq!(res.status(), 404);
+
+    let res = client.get(format!("http://localhost:8080/answer/{}", second.id)).send().await.unwrap();
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.json::<Answer>().await.unwrap().content, "Another answer");
+}
+
+async fn get_question_with_answers(first: &Answer) {
+    let client = reqwest::Client::new();
+    let url = format!("http://localhost:8080/question/{}", first.question_id);
+
+    let res = client.get(format!("{}?include=answers,author", url)).send().await.unwrap();
+    assert_eq!(res.status(), 200);
+    let detail = res.json::<serde_json::Value>().await.unwrap();
+    assert_eq!(detail["id"], first.question_id);
+    let answers = detail["answers"].as_array().unwrap();
+    assert_eq!(answers.len(), 2);
+    assert_eq!(answers[0]["id"], first.id);
+    assert!(detail["author"].is_object());
+    assert!(answers[0]["author"].is_object());
+
+    // only what is listed gets embedded
+    let detail = client.get(format!("{}?include=answers", url))
+        .send()
+        .await
+        .unwrap()
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    assert!(detail["author"].is_null());
+    assert!(detail["answers"][0]["author"].is_null());
+
+    let detail = client.get(&url).send().await.unwrap().json::<serde_json::Value>().await.unwrap();
+    assert!(detail.get("answers").is_none());
+    assert!(detail["author"].is_object());
+
+    let res = client.get(format!("{}?include=comments", url)).send().await.unwrap();
+    assert_eq!(res.status(), 400);
+}
+
+async fn accept_answer(token: &Token, first: &Answer) {
+    let client = reqwest::Client::new();
+    let url = format!("http://localhost:8080/question/{}/answers?sort=oldest", first.question_id);
+    let answers = client.get(&url).send().await.unwrap().json::<Vec<Answer>>().await.unwrap();
+    assert!(answers.iter().all(|a| !a.is_accepted));
+    let second = &answers[1];
+
+    let accept = format!("http://localhost:8080/question/{}/accept", first.question_id);
+    let res = client.post(format!("{}/{}", accept, second.id))
+        .header("Authorization", token.0.clone())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+
+    // pinned first even though it is the newer one
+    let answers = client.get(&url).send().await.unwrap().json::<Vec<Answer>>().await.unwrap();
+    assert_eq!(answers[0].id, second.id);
+    assert!(answers[0].is_accepted);
+    assert!(!answers[1].is_accepted);
+
+    let res = client.post(format!("{}/9999", accept))
+        .header("Authorization", token.0.clone())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 404);
+
+    let res = client.delete(&accept)
+        .header("Authorization", token.0.clone())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+
+    let answer = client.get(format!("http://localhost:8080/answer/{}", second.id))
+        .send()
+        .await
+        .unwrap()
+        .json::<Answer>()
+        .await
+        .unwrap();
+    assert!(!answer.is_accepted);
+}
+async fn vote(owner: &Token, answer: &Answer) {
+    let client = reqwest::Client::new();
+    let mut voters = Vec::new();
+    for i in 0..3 {
+        let user = UserDTO { email: format!("voter{}@email.com", i), password: "password".to_string() };
+        let res = client.post("http://localhost:8080/registration").json(&user).send().await.unwrap();
+        assert_eq!(res.status(), 200);
+        let res = client.post("http://localhost:8080/login").json(&user).send().await.unwrap();
+        assert_eq!(res.status(), 200);
+        voters.push(Token(res.json::<TokenPair>().await.unwrap().access_token));
+    }
+
+    let question_vote = format!("http://localhost:8080/question/{}/vote", answer.question_id);
+    let cast = |token: &Token, url: &str, vote: &str| client.post(url)
+        .header("Authorization", token.0.clone())
+        .json(&serde_json::json!({ "vote": vote }))
+        .send();
+
+    let res = cast(&voters[0], &question_vote, "up").await.unwrap();
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.json::<serde_json::Value>().await.unwrap()["score"], 1);
+
+    // voting again changes the vote, it doesn't add one
+    let res = cast(&voters[0], &question_vote, "down").await.unwrap();
+    assert_eq!(res.json::<serde_json::Value>().await.unwrap()["score"], -1);
+    let res = cast(&voters[0], &question_vote, "down").await.unwrap();
+    assert_eq!(res.json::<serde_json::Value>().await.unwrap()["score"], -1);
+
+    let res = cast(&voters[0], &question_vote, "sideways").await.unwrap();
+    assert_eq!(res.status(), 400);
+
+    let res = cast(owner, &question_vote, "up").await.unwrap();
+    assert_eq!(res.status(), 403);
+
+    let res = client.delete(&question_vote)
+        .header("Authorization", voters[0].0.clone())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.json::<serde_json::Value>().await.unwrap()["score"], 0);
+    let res = client.delete(&question_vote)
+        .header("Authorization", voters[0].0.clone())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 404);
+
+    // every concurrent vote is counted
+    let answer_vote = format!("http://localhost:8080/answer/{}/vote", answer.id);
+    let results = futures_util::future::join_all(voters.iter().map(|voter| cast(voter, &answer_vote, "up"))).await;
+    assert!(results.into_iter().all(|res| res.unwrap().status() == 200));
+
+    let voted = client.get(format!("http://localhost:8080/answer/{}", answer.id))
+        .send()
+        .await
+        .unwrap()
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    assert_eq!(voted["score"], 3);
+
+    let question = client.get(format!("http://localhost:8080/question/{}", answer.question_id))
+        .send()
+        .await
+        .unwrap()
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    assert_eq!(question["score"], 0);
+
+    let res = cast(&voters[0], "http://localhost:8080/answer/9999/vote", "up").await.unwrap();
+    assert_eq!(res.status(), 404);
+}
+
+async fn reputation(owner: &Token, first: &Answer) {
+    let client = reqwest::Client::new();
+    let mut voters = Vec::new();
+    for i in 0..3 {
+        let user = UserDTO { email: format!("voter{}@email.com", i), password: "password".to_string() };
+        let res = client.post("http://localhost:8080/login").json(&user).send().await.unwrap();
+        voters.push(Token(res.json::<TokenPair>().await.unwrap().access_token));
+    }
+    let reputation_of = |token: &Token| {
+        let request = client.get("http://localhost:8080/account/me").header("Authorization", token.0.clone());
+        async move { request.send().await.unwrap().json::<serde_json::Value>().await.unwrap()["reputation"].clone() }
+    };
+
+    // a question (1), two answers (2 each) and three upvotes (10 each), the
+    // votes taken back and accepting its own answer earned nothing
+    assert_eq!(reputation_of(owner).await, 35);
+    assert_eq!(reputation_of(&voters[0]).await, 0);
+
+    let question_url = format!("http://localhost:8080/question/{}", first.question_id);
+    let question = client.get(&question_url).send().await.unwrap().json::<serde_json::Value>().await.unwrap();
+    let edit = |token: &Token| client.put(&question_url)
+        .header("Authorization", token.0.clone())
+        .json(&question)
+        .send();
+
+    let res = edit(&voters[0]).await.unwrap();
+    assert_eq!(res.status(), 403);
+
+    // without the reputation to edit, a missing post is still a 404
+    let res = client.put("http://localhost:8080/question/999999")
+        .header("Authorization", voters[0].0.clone())
+        .json(&question)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 404);
+    let res = client.put("http://localhost:8080/answer/999999")
+        .header("Authorization", voters[0].0.clone())
+        .json(&AnswerDTO { content: "Edited".to_string(), question_id: first.question_id })
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 404);
+
+    let answer = AnswerDTO { content: "Answer from a voter".to_string(), question_id: first.question_id };
+    let answer: Answer = post_entity(&voters[0], &answer, "http://localhost:8080/answer").await;
+    let answer_vote = format!("http://localhost:8080/answer/{}/vote", answer.id);
+    for voter in [owner, &voters[1], &voters[2]] {
+        let res = client.post(&answer_vote)
+            .header("Authorization", voter.0.clone())
+            .json(&serde_json::json!({ "vote": "up" }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 200);
+    }
+    assert_eq!(reputation_of(&voters[0]).await, 32);
+
+    let res = edit(&voters[0]).await.unwrap();
+    assert_eq!(res.status(), 200);
+
+    // an upvote turned into a downvote
+    client.post(&answer_vote)
+        .header("Authorization", voters[1].0.clone())
+        .json(&serde_json::json!({ "vote": "down" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(reputation_of(&voters[0]).await, 20);
+    let res = edit(&voters[0]).await.unwrap();
+    assert_eq!(res.status(), 403);
+
+    let accept = format!("{}/accept", question_url);
+    client.post(format!("{}/{}", accept, answer.id))
+        .header("Authorization", owner.0.clone())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(reputation_of(&voters[0]).await, 35);
+    client.delete(&accept).header("Authorization", owner.0.clone()).send().await.unwrap();
+    assert_eq!(reputation_of(&voters[0]).await, 20);
+
+    // everything the answer earned goes with it
+    let res = client.delete(format!("http://localhost:8080/answer/{}", answer.id))
+        .header("Authorization", voters[0].0.clone())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    assert_eq!(reputation_of(&voters[0]).await, 0);
+    assert_eq!(reputation_of(owner).await, 35);
+
+    let res = client.post("http://localhost:8080/accounts/reputation/replay")
+        .header("Authorization", owner.0.clone())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 403);
+
+    // a replay recomputes totals from the events, whatever they were before
+    execute_sql("UPDATE public.accounts SET role = 'admin' WHERE email = 'voter2@email.com'").await;
+    execute_sql("UPDATE public.accounts SET reputation = 0 WHERE email = 'test@email.com'").await;
+    assert_eq!(reputation_of(owner).await, 0);
+    let admin = UserDTO { email: "voter2@email.com".to_string(), password: "password".to_string() };
+    let res = client.post("http://localhost:8080/login").json(&admin).send().await.unwrap();
+    let admin = Token(res.json::<TokenPair>().await.unwrap().access_token);
+    let res = client.post("http://localhost:8080/accounts/reputation/replay")
+        .header("Authorization", admin.0.clone())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    assert_eq!(reputation_of(owner).await, 35);
+    assert_eq!(reputation_of(&voters[0]).await, 0);
+
+    // a deleted account's votes stop counting, for the score and the author
+    let leaving = UserDTO { email: "voter3@email.com".to_string(), password: "password".to_string() };
+    let res = client.post("http://localhost:8080/registration").json(&leaving).send().await.unwrap();
+    assert_eq!(res.status(), 200);
+    let res = client.post("http://localhost:8080/login").json(&leaving).send().await.unwrap();
+    let voter = Token(res.json::<TokenPair>().await.unwrap().access_token);
+    for (url, vote) in [
+        (format!("http://localhost:8080/answer/{}/vote", first.id), "up"),
+        (format!("{}/vote", question_url), "down"),
+    ] {
+        let res = client.post(url)
+            .header("Authorization", voter.0.clone())
+            .json(&serde_json::json!({ "vote": vote }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 200);
+    }
+    assert_ne!(reputation_of(owner).await, 35);
+    let res = client.delete("http://localhost:8080/account")
+        .header("Authorization", voter.0.clone())
+        .json(&serde_json::json!({ "password": leaving.password }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    assert_eq!(reputation_of(owner).await, 35);
+    let answer = client.get(format!("http://localhost:8080/answer/{}", first.id))
+        .send()
+        .await
+        .unwrap()
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    assert_eq!(answer["score"], 3);
+
+    // and the ledger agrees
+    let res = client.post("http://localhost:8080/accounts/reputation/replay")
+        .header("Authorization", admin.0.clone())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    assert_eq!(reputation_of(owner).await, 35);
+}
+
+async fn comments(owner: &Token, first: &Answer) {
+    let client = reqwest::Client::new();
+    let voter = UserDTO { email: "voter0@email.com".to_string(), password: "password".to_string() };
+    let res = client.post("http://localhost:8080/login").json(&voter).send().await.unwrap();
+    let voter = Token(res.json::<TokenPair>().await.unwrap().access_token);
+    let comment = |token: &Token, url: String, content: &str| client.post(url)
+        .header("Authorization", token.0.clone())
+        .json(&serde_json::json!({ "content": content }))
+        .send();
+
+    let question_comments = format!("http://localhost:8080/question/{}/comments", first.question_id);
+    let res = comment(owner, question_comments.clone(), "  Which version is this about?  ").await.unwrap();
+    assert_eq!(res.status(), 200);
+    let on_question = res.json::<serde_json::Value>().await.unwrap();
+    assert_eq!(on_question["content"], "Which version is this about?");
+    assert_eq!(on_question["question_id"], first.question_id);
+
+    let answer_comments = format!("http://localhost:8080/answer/{}/comments", first.id);
+    let res = comment(&voter, answer_comments.clone(), "\n\tWorks for me\r\n").await.unwrap();
+    assert_eq!(res.status(), 200);
+    let on_answer = res.json::<serde_json::Value>().await.unwrap();
+    assert_eq!(on_answer["content"], "Works for me");
+
+    let res = comment(&voter, answer_comments.clone(), "   ").await.unwrap();
+    assert_eq!(res.status(), 400);
+    let res = comment(&voter, answer_comments, &"a".repeat(601)).await.unwrap();
+    assert_eq!(res.status(), 400);
+    let res = comment(&voter, "http://localhost:8080/question/9999/comments".to_string(), "Lost").await.unwrap();
+    assert_eq!(res.status(), 404);
+
+    let comment_url = |comment: &serde_json::Value| format!("http://localhost:8080/comment/{}", comment["id"]);
+    let res = client.put(comment_url(&on_question))
+        .header("Authorization", voter.0.clone())
+        .json(&serde_json::json!({ "content": "Not mine to edit" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 403);
+    let res = client.put(comment_url(&on_question))
+        .header("Authorization", owner.0.clone())
+        .json(&serde_json::json!({ "content": "\nWhich version of the api is this about?\n" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let edited = res.json::<serde_json::Value>().await.unwrap();
+    assert!(edited["updated_on"].is_string());
+    assert_eq!(edited["content"], "Which version of the api is this about?");
+
+    let url = format!("http://localhost:8080/question/{}", first.question_id);
+    let detail = client.get(format!("{}?include=answers", url))
+        .send()
+        .await
+        .unwrap()
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    assert_eq!(detail["comments"][0]["content"], "Which version of the api is this about?");
+    let answer = detail["answers"].as_array().unwrap().iter().find(|a| a["id"] == first.id).unwrap();
+    assert_eq!(answer["comments"][0]["id"], on_answer["id"]);
+
+    let detail = client.get(&url).send().await.unwrap().json::<serde_json::Value>().await.unwrap();
+    assert!(detail.get("comments").is_none());
+
+    let res = client.delete(comment_url(&on_answer))
+        .header("Authorization", voter.0.clone())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let res = client.delete(comment_url(&on_answer))
+        .header("Authorization", voter.0.clone())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 403);
+}
+
+async fn delete_answer_and_question(token: &Token, question: &QuestionDTO) {
+    let client = reqwest::Client::new();
+    let created: Question = post_entity(token, question, "http://localhost:8080/question").await;
+    let answer = AnswerDTO { content: "Short lived".to_string(), question_id: created.id };
+    let first: Answer = post_entity(token, &answer, "http://localhost:8080/answer").await;
+
+    let res = client.delete(format!("http://localhost:8080/answer/{}", first.id))
+        .header("Authorization", token.0.clone())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+
+    let res = client.get(format!("http://localhost:8080/answer/{}", first.id)).send().await.unwrap();
+    assert_eq!(res.status(), 404);
+
+    // a question with answers can be deleted, the answers go with it
+    let second: Answer = post_entity(token, &answer, "http://localhost:8080/answer").await;
+    let res = client.delete(format!("http://localhost:8080/question/{}", created.id))
+        .header("Authorization", token.0.clone())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+
+    let res = client.get(format!("http://localhost:8080/answer/{}", second.id)).send().await.unwrap();
+    assert_eq!(res.status(), 404);
+}
+
+async fn update_profile(token: &Token) {
+    let client = reqwest::Client::new();
+    let res = client.put("http://localhost:8080/account/me")
+        .header("Authorization", token.0.clone())
+        .json(&serde_json::json!({ "display_name": "Tester", "bio": "I test things" }))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), 200);
+    let profile = res.json::<serde_json::Value>().await.unwrap();
+    assert_eq!(profile["display_name"], "Tester");
+
+    // the public profile never shows the email
+    let res = client.get(format!("http://localhost:8080/users/{}", profile["id"]))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), 200);
+    let public = res.json::<serde_json::Value>().await.unwrap();
+    assert_eq!(public["display_name"], "Tester");
+    assert!(public.get("email").is_none());
+    assert!(public.get("password").is_none());
+}
+
+async fn change_password(token: &Token, user: &UserDTO) {
+    let client = reqwest::Client::new();
+    let change = PasswordChange {
+        old_password: user.password.clone(),
+        new_password: "new password".to_string(),
+    };
+
+    let res = client.put("http://localhost:8080/account/password")
+        .header("Authorization", token.0.clone())
+        .json(&change)
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), 200);
+
+    let res = client.post("http://localhost:8080/login")
+        .json(&user)
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), 401);
+
+    let res = client.post("http://localhost:8080/login")
+        .json(&UserDTO { email: user.email.clone(), password: change.new_password })
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), 200);
+}
+
+async fn list_accounts_forbidden(token: &Token) {
+    let client = reqwest::Client::new();
+    let res = client.get("http://localhost:8080/accounts")
+        .header("Authorization", token.0.clone())
+        .send()
+        .await
+        .unwrap();
+
+    // authenticated, but only admins can manage accounts
+    assert_eq!(res.status(), 403);
+
+    let res = client.get("http://localhost:8080/accounts")
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), 401);
+}
+
+async fn use_api_key(token: &Token, question: &QuestionDTO) {
+    let client = reqwest::Client::new();
+    // an out of range expiry is refused instead of overflowing
+    let res = client.post("http://localhost:8080/account/api-keys")
+        .header("Authorization", token.0.clone())
+        .json(&serde_json::json!({ "name": "bot", "expires_in_days": i64::MAX }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+
+    let res = client.post("http://localhost:8080/account/api-keys")
+        .header("Authorization", token.0.clone())
+        .json(&serde_json::json!({ "name": "bot", "scopes": ["answers:write"] }))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), 201);
+    let created = res.json::<serde_json::Value>().await.unwrap();
+    let key = created["key"].as_str().unwrap().to_string();
+    let id = created["id"].as_i64().unwrap();
+
+    // the key is only shown once
+    let res = client.get("http://localhost:8080/account/api-keys")
+        .header("Authorization", token.0.clone())
+        .send()
+        .await
+        .unwrap();
+    let keys = res.json::<serde_json::Value>().await.unwrap();
+    assert_eq!(keys.as_array().unwrap().len(), 1);
+    assert!(keys[0].get("key").is_none());
+
+    // the key can answer, but wasn't granted questions:write
+    let res = client.post("http://localhost:8080/question")
+        .header("X-Api-Key", key.clone())
+        .json(&question)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 403);
+
+    let res = client.delete(format!("http://localhost:8080/account/api-keys/{}", id))
+        .header("Authorization", format!("ApiKey {}", key))
+        .send()
+        .await
+        .unwrap();
+    // revoking needs account:write too
+    assert_eq!(res.status(), 403);
+
+    // even with account:write a key can't mint keys that outlive its revocation
+    let res = client.post("http://localhost:8080/account/api-keys")
+        .header("Authorization", token.0.clone())
+        .json(&serde_json::json!({ "name": "full access" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 201);
+    let full_access = res.json::<serde_json::Value>().await.unwrap();
+    let res = client.post("http://localhost:8080/account/api-keys")
+        .header("X-Api-Key", full_access["key"].as_str().unwrap())
+        .json(&serde_json::json!({ "name": "minted by a key" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 403);
+    let res = client.delete(format!("http://localhost:8080/account/api-keys/{}", full_access["id"]))
+        .header("Authorization", token.0.clone())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+
+    let res = client.delete(format!("http://localhost:8080/account/api-keys/{}", id))
+        .header("Authorization", token.0.clone())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+
+    let res = client.get("http://localhost:8080/account/api-keys")
+        .header("X-Api-Key", key)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 401);
+}
+
+async fn use_scoped_token(token: &Token, question: &QuestionDTO) {
+    let client = reqwest::Client::new();
+    let res = client.post("http://localhost:8080/token/scoped")
+        .header("Authorization", token.0.clone())
+        .json(&serde_json::json!({ "scopes": ["account:read"] }))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), 200);
+    let tokens = res.json::<TokenPair>().await.unwrap();
+
+    let res = client.get("http://localhost:8080/account/me")
+        .header("Authorization", tokens.access_token.clone())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+
+    // read only: writing is refused, and so is widening the scopes
+    let res = client.post("http://localhost:8080/question")
+        .header("Authorization", tokens.access_token.clone())
+        .json(&question)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 403);
+
+    let res = client.post("http://localhost:8080/token/scoped")
+        .header("Authorization", tokens.access_token)
+        .json(&serde_json::json!({ "scopes": ["questions:write"] }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 403);
+}
+
+async fn rotate_keys(token: &Token, config: &config::Config) {
+    let client = reqwest::Client::new();
+    let footer = |kid: &str| serde_json::json!({ "kid": kid }).to_string();
+    let claims = paseto::v2::local::decrypt_paseto(
+        &token.0,
+        Some(&footer(&config.paseto_key.id)),
+        &config.paseto_key.key,
+    )
+    .unwrap();
+    let me = |token: String| client.get("http://localhost:8080/account/me")
+        .header("Authorization", token)
+        .send();
+
+    // the same session, as a token encrypted before the rotation
+    let retired = paseto::v2::local::local_paseto(
+        &claims,
+        Some(&footer(RETIRED_PASETO_KEY_ID)),
+        RETIRED_PASETO_KEY.as_bytes(),
+    )
+    .unwrap();
+    let res = me(retired).await.unwrap();
+    assert_eq!(res.status(), 200);
+
+    let unknown = paseto::v2::local::local_paseto(
+        &claims,
+        Some(&footer("unknown")),
+        RETIRED_PASETO_KEY.as_bytes(),
+    )
+    .unwrap();
+    let res = me(unknown).await.unwrap();
+    assert_eq!(res.status(), 401);
+
+    // the footer names the key, the token has to have been made with it
+    let mismatched = paseto::v2::local::local_paseto(
+        &claims,
+        Some(&footer(&config.paseto_key.id)),
+        RETIRED_PASETO_KEY.as_bytes(),
+    )
+    .unwrap();
+    let res = me(mismatched).await.unwrap();
+    assert_eq!(res.status(), 401);
+}
+
+async fn use_public_tokens() {
+    // a typo in the mode stops the server from starting
+    std::env::set_var("PASETO_MODE", "publc");
+    let typo = config::Config::new();
+    std::env::set_var("PASETO_MODE", "local");
+    assert!(matches!(typo, Err(e) if e.to_string().contains("must be local or public")));
+
+    let client = reqwest::Client::new();
+    let res = client.get("http://localhost:8081/.well-known/paseto-keys").send().await.unwrap();
+    assert_eq!(res.status(), 200);
+    let keys = res.json::<serde_json::Value>().await.unwrap()["keys"].clone();
+    assert_eq!(keys[0]["kid"], "1");
+    assert_eq!(keys[0]["purpose"], "public");
+    let public_key = data_encoding::BASE64URL_NOPAD
+        .decode(keys[0]["public_key"].as_str().unwrap().as_bytes())
+        .unwrap();
+    assert_eq!(data_encoding::HEXLOWER.encode(&public_key), SIGNING_PUBLIC_KEY);
+
+    let user = UserDTO { email: "public@email.com".to_string(), password: "password".to_string() };
+    let res = client.post("http://localhost:8081/registration").json(&user).send().await.unwrap();
+    assert_eq!(res.status(), 200);
+    let res = client.post("http://localhost:8081/login").json(&user).send().await.unwrap();
+    assert_eq!(res.status(), 200);
+    let access_token = res.json::<TokenPair>().await.unwrap().access_token;
+    assert!(access_token.starts_with("v2.public."));
+
+    // anyone holding the published key can check the token
+    let footer = serde_json::json!({ "kid": keys[0]["kid"] }).to_string();
+    let claims = paseto::v2::public::verify_paseto(&access_token, Some(&footer), &public_key).unwrap();
+    let claims = serde_json::from_str::<serde_json::Value>(&claims).unwrap();
+    let res = client.get("http://localhost:8081/account/me")
+        .header("Authorization", access_token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.json::<serde_json::Value>().await.unwrap()["id"], claims["account_id"]);
+}
+
+async fn login_with_oidc() {
+    // each redirect is followed by hand, to see where it goes
+    let client = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .unwrap();
+
+    let res = client.get("http://localhost:8080/login/oidc").send().await.unwrap();
+    assert_eq!(res.status(), 302);
+    let authorize = res.headers()["location"].to_str().unwrap().to_string();
+    assert!(authorize.starts_with(mock_issuer::ISSUER));
+    assert!(authorize.contains("code_challenge_method=S256"));
+    let set_cookie = res.headers()["set-cookie"].to_str().unwrap().to_string();
+    assert!(set_cookie.contains("HttpOnly"));
+    assert!(set_cookie.contains("SameSite=Lax"));
+    let cookie = set_cookie.split(';').next().unwrap().to_string();
+
+    let res = client.get(authorize).send().await.unwrap();
+    assert_eq!(res.status(), 302);
+    let callback = res.headers()["location"].to_str().unwrap().to_string();
+
+    // a callback url alone, without the browser that started the login, is refused
+    let res = client.get(&callback).send().await.unwrap();
+    assert_eq!(res.status(), 400);
+    let (_, other_cookie) = oidc_callback(&client, mock_issuer::EMAIL).await;
+    let res = client.get(&callback).header("Cookie", other_cookie).send().await.unwrap();
+    assert_eq!(res.status(), 400);
+
+    // and doesn't use up the state of the browser it came from
+    let res = client.get(&callback).header("Cookie", &cookie).send().await.unwrap();
+    assert_eq!(res.status(), 200);
+    assert!(res.headers()["set-cookie"].to_str().unwrap().contains("Max-Age=0"));
+    let tokens = res.json::<TokenPair>().await.unwrap();
+
+    // the account was created on the spot for the provider's user
+    let res = client.get("http://localhost:8080/account/me")
+        .header("Authorization", tokens.access_token.clone())
+        .send()
+        .await
+        .unwrap();
+    let profile = res.json::<serde_json::Value>().await.unwrap();
+    assert_eq!(profile["email"], mock_issuer::EMAIL);
+
+    // the state was used up by the first callback
+    let res = client.get(&callback).header("Cookie", &cookie).send().await.unwrap();
+    assert_eq!(res.status(), 400);
+
+    // an unverified email is never linked to the local account using it
+    let (callback, cookie) = oidc_callback(&client, "test@email.com").await;
+    let res = client.get(callback).header("Cookie", cookie).send().await.unwrap();
+    assert_eq!(res.status(), 409);
+
+    // two first logins racing end up on the same new account
+    let (first, second) = tokio::join!(
+        async {
+            let (callback, cookie) = oidc_callback(&client, "racing@email.com").await;
+            client.get(callback).header("Cookie", cookie).send().await.unwrap()
+        },
+        async {
+            let (callback, cookie) = oidc_callback(&client, "racing@email.com").await;
+            client.get(callback).header("Cookie", cookie).send().await.unwrap()
+        },
+    );
+    assert_eq!(first.status(), 200);
+    assert_eq!(second.status(), 200);
+    let mut ids = Vec::new();
+    let mut racing = Vec::new();
+    for res in [first, second] {
+        let tokens = res.json::<TokenPair>().await.unwrap();
+        racing.push(tokens.access_token.clone());
+        let profile = client.get("http://localhost:8080/account/me")
+            .header("Authorization", tokens.access_token)
+            .send()
+            .await
+            .unwrap()
+            .json::<serde_json::Value>()
+            .await
+            .unwrap();
+        ids.push(profile["id"].clone());
+    }
+    assert_eq!(ids[0], ids[1]);
+
+    // accounts made by an oidc login have no password to confirm a deletion
+    // with, a fresh login through the provider does instead
+    let delete = |token: &str| client.delete("http://localhost:8080/account")
+        .header("Authorization", token.to_string())
+        .json(&serde_json::json!({}))
+        .send();
+    let res = client.post("http://localhost:8080/token/scoped")
+        .header("Authorization", tokens.access_token.clone())
+        .json(&serde_json::json!({ "scopes": ["account:write"] }))
+        .send()
+        .await
+        .unwrap();
+    let scoped = res.json::<TokenPair>().await.unwrap();
+    let res = delete(&scoped.access_token).await.unwrap();
+    assert_eq!(res.status(), 403);
+    execute_sql(&format!(
+        "UPDATE public.sessions SET created_on = NOW() - INTERVAL '1 hour'
+          WHERE account_id = (SELECT id FROM public.accounts WHERE email = '{}')",
+        mock_issuer::EMAIL
+    ))
+    .await;
+    let res = delete(&tokens.access_token).await.unwrap();
+    assert_eq!(res.status(), 403);
+
+    let res = delete(&racing[0]).await.unwrap();
+    assert_eq!(res.status(), 200);
+    let res = client.get("http://localhost:8080/account/me")
+        .header("Authorization", racing[1].clone())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 401);
+}
+
+// walks a login up to the callback url, for the user `login_hint` names.
+// Also returns the state cookie the browser would send along.
+async fn oidc_callback(client: &Client, login_hint: &str) -> (String, String) {
+    let res = client.get("http://localhost:8080/login/oidc").send().await.unwrap();
+    let authorize = res.headers()["location"].to_str().unwrap().to_string();
+    let cookie = res.headers()["set-cookie"].to_str().unwrap().split(';').next().unwrap().to_string();
+    let res = client.get(format!("{}&login_hint={}", authorize, login_hint)).send().await.unwrap();
+    (res.headers()["location"].to_str().unwrap().to_string(), cookie)
+}
+
+async fn sign_out_device(token: &Token, user: &UserDTO) {
+    let client = reqwest::Client::new();
+    // a second device, the password was changed earlier on
+    let res = client.post("http://localhost:8080/login")
+        .header("User-Agent", "second-device")
+        .json(&UserDTO { email: user.email.clone(), password: "new password".to_string() })
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let other = res.json::<TokenPair>().await.unwrap();
+
+    let res = client.get("http://localhost:8080/account/sessions")
+        .header("Authorization", token.0.clone())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let sessions = res.json::<Vec<serde_json::Value>>().await.unwrap();
+    let device = sessions.iter()
+        .find(|session| session["user_agent"] == "second-device")
+        .unwrap();
+    assert_eq!(device["current"], false);
+    assert!(sessions.iter().any(|session| session["current"] == true));
+
+    let res = client.delete(format!("http://localhost:8080/account/sessions/{}", device["id"]))
+        .header("Authorization", token.0.clone())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+
+    // the signed out device loses both its access and its refresh token
+    let res = client.get("http://localhost:8080/account/me")
+        .header("Authorization", other.access_token.clone())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 401);
+
+    let res = client.post("http://localhost:8080/token/refresh")
+        .json(&RefreshRequest { refresh_token: other.refresh_token })
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 401);
+
+    let res = client.delete(format!("http://localhost:8080/account/sessions/{}", device["id"]))
+        .header("Authorization", token.0.clone())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 404);
+
+    // seen a moment ago, listing again doesn't move it
+    let current = |sessions: Vec<serde_json::Value>| sessions.into_iter()
+        .find(|session| session["current"] == true)
+        .unwrap();
+    let list = || client.get("http://localhost:8080/account/sessions")
+        .header("Authorization", token.0.clone())
+        .send();
+    let before = current(list().await.unwrap().json().await.unwrap());
+    let after = current(list().await.unwrap().json().await.unwrap());
+    assert_eq!(before["last_seen_on"], after["last_seen_on"]);
+
+    // a device whose refresh token expired is signed out, it isn't listed
+    let res = client.post("http://localhost:8080/login")
+        .header("User-Agent", "expired-device")
+        .json(&UserDTO { email: user.email.clone(), password: "new password".to_string() })
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    execute_sql(
+        "UPDATE public.refresh_tokens SET expires_on = NOW() - INTERVAL '1 second'
+          WHERE family_id = (SELECT family_id FROM public.sessions WHERE user_agent = 'expired-device')"
+    ).await;
+    let sessions: Vec<serde_json::Value> = list().await.unwrap().json().await.unwrap();
+    assert!(sessions.iter().all(|session| session["user_agent"] != "expired-device"));
+}
+
+async fn login_with_two_factor() {
+    let client = reqwest::Client::new();
+    let user = UserDTO { email: "twofactor@email.com".to_string(), password: "password".to_string() };
+    let res = client.post("http://localhost:8080/registration").json(&user).send().await.unwrap();
+    assert_eq!(res.status(), 200);
+    let res = client.post("http://localhost:8080/login").json(&user).send().await.unwrap();
+    let token = Token(res.json::<TokenPair>().await.unwrap().access_token);
+
+    let res = client.post("http://localhost:8080/account/2fa")
+        .header("Authorization", token.0.clone())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let secret = res.json::<serde_json::Value>().await.unwrap()["secret"].as_str().unwrap().to_string();
+
+    let now = chrono::Utc::now().timestamp();
+    let res = client.post("http://localhost:8080/account/2fa/confirm")
+        .header("Authorization", token.0.clone())
+        .json(&serde_json::json!({ "code": totp_code(&secret, now) }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let recovery_codes = res.json::<serde_json::Value>().await.unwrap()["recovery_codes"].clone();
+
+    // the password only gets a challenge now
+    let challenge = || async {
+        let res = client.post("http://localhost:8080/login").json(&user).send().await.unwrap();
+        assert_eq!(res.status(), 200);
+        let challenge = res.json::<serde_json::Value>().await.unwrap();
+        assert_eq!(challenge["two_factor_required"], true);
+        challenge["challenge_token"].as_str().unwrap().to_string()
+    };
+    let answer = |challenge_token: String, code: String| client.post("http://localhost:8080/login/2fa")
+        .json(&serde_json::json!({ "challenge_token": challenge_token, "code": code }))
+        .send();
+
+    // the confirmation used the current step, the next one is still in the window
+    let code = totp_code(&secret, now + 30);
+    let challenge_token = challenge().await;
+    let res = answer(challenge_token.clone(), code.clone()).await.unwrap();
+    assert_eq!(res.status(), 200);
+    let tokens = res.json::<TokenPair>().await.unwrap();
+    let res = client.get("http://localhost:8080/account/me")
+        .header("Authorization", tokens.access_token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+
+    // a challenge is good for one answer
+    let res = answer(challenge_token, totp_code(&secret, now + 60)).await.unwrap();
+    assert_eq!(res.status(), 401);
+
+    // and a code for one login
+    let res = answer(challenge().await, code).await.unwrap();
+    assert_eq!(res.status(), 401);
+
+    // a recovery code gets in once
+    let recovery_code = recovery_codes[0].as_str().unwrap().to_string();
+    let res = answer(challenge().await, recovery_code.clone()).await.unwrap();
+    assert_eq!(res.status(), 200);
+    let res = answer(challenge().await, recovery_code).await.unwrap();
+    assert_eq!(res.status(), 401);
+
+    let res = client.delete("http://localhost:8080/account/2fa")
+        .header("Authorization", token.0.clone())
+        .json(&serde_json::json!({ "code": recovery_codes[1] }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+
+    // back to a password login
+    let res = client.post("http://localhost:8080/login").json(&user).send().await.unwrap();
+    assert_eq!(res.status(), 200);
+    assert!(res.json::<serde_json::Value>().await.unwrap().get("access_token").is_some());
+}
+
+// RFC 6238 code for `unix_time`, as an authenticator app would show it
+fn totp_code(secret: &str, unix_time: i64) -> String {
+    use hmac::{Hmac, Mac};
+
+    let key = data_encoding::BASE32_NOPAD.decode(secret.as_bytes()).unwrap();
+    let mut mac = Hmac::<sha1::Sha1>::new_from_slice(&key).unwrap();
+    mac.update(&(unix_time / 30).to_be_bytes());
+    let digest = mac.finalize().into_bytes();
+    let offset = (digest[19] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([
+        digest[offset] & 0x7f,
+        digest[offset + 1],
+        digest[offset + 2],
+        digest[offset + 3],
+    ]);
+    format!("{:06}", binary % 1_000_000)
+}
+
+async fn delete_account(question: &QuestionDTO) {
+    let client = reqwest::Client::new();
+    let user = UserDTO { email: "leaving@email.com".to_string(), password: "password".to_string() };
+    let res = client.post("http://localhost:8080/registration")
+        .json(&user)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+
+    let tokens = login_user(&user).await;
+    let token = Token(tokens.access_token);
+    let created: Question = post_entity(&token, question, "http://localhost:8080/question").await;
+
+    let res = client.get("http://localhost:8080/account/export")
+        .header("Authorization", token.0.clone())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let export = res.json::<serde_json::Value>().await.unwrap();
+    assert_eq!(export["account"]["email"], user.email);
+    assert_eq!(export["questions"][0]["id"], created.id);
+
+    let res = client.delete("http://localhost:8080/account")
+        .header("Authorization", token.0.clone())
+        .json(&serde_json::json!({ "password": "wrong password" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 401);
+
+    let res = client.delete("http://localhost:8080/account")
+        .header("Authorization", token.0.clone())
+        .json(&serde_json::json!({ "password": user.password }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+
+    // the account is gone along with its sessions
+    let res = client.get("http://localhost:8080/account/me")
+        .header("Authorization", token.0.clone())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 401);
+
+    let res = client.post("http://localhost:8080/login")
+        .json(&user)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 401);
+
+    // its question is still there, credited to the placeholder
+    let res = client.get(format!("http://localhost:8080/question/{}", created.id))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let kept = res.json::<serde_json::Value>().await.unwrap();
+    assert_eq!(kept["author"]["display_name"], "deleted user");
+}
+
+async fn logout_user(token: &Token, question: &QuestionDTO) {
+    let client = reqwest::Client::new();
+    let res = client.post("http://localhost:8080/logout")
+        .header("Authorization", token.0.clone())
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), 200);
+
+    // the revoked token can't be used anymore
+    let res = client.post("http://localhost:8080/question")
+        .header("Authorization", token.0.clone())
+        .json(&question)
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), 401);
+}
+
+async fn create_question(token: &Token, question: &QuestionDTO) -> Question {
+    let res: Question = post_entity(token, &question, "http://localhost:8080/question").await;
+    assert_eq!(res.id, 1);
+    assert_eq!(res.content, question.content);
+
+    res
+}
+
+async fn create_answer(token: &Token, answer: &AnswerDTO) -> Answer {
+    let res: Answer = post_entity(token, &answer, "http://localhost:8080/answer").await;
+    assert_eq!(res.id, 1);
+    assert_eq!(res.content, answer.content);
+    assert_eq!(res.question_id, answer.question_id);
+
+    res
+}
+
+// below, we use Higher-Ranked Trait Bounds (HRTB) for S generic types, but why?
+// This is used when the trait implementation must be valid for any lifetime 'de,
+// not just a specific one. In this case, it means that S must implement the 
+// Deserialize trait for any lifetime, not just a specific lifetime 'a.
+// When S: for<'de> Deserialize<'de> is used, it means S must be a type that can 
+// be deserialized from any possible lifetime 'de. This is a requirement for the
+// json method from the reqwest library, because it doesn't know the exact 
+// lifetime of the data it will be receiving from the network, so it requires a 
+// type that can handle any possible lifetime.
+async fn post_entity<'a, T, S>(token: &'a Token, entity: &'a T, action_url: &'a str) -> S
+    where T: Serialize + 'a, S: for<'b> Deserialize<'b> {
+    let client = Client::new();
+    let response = client.post(action_url)
+        .header("Authorization", token.0.clone())
+        .json(&entity)
+        .send()
+        .await
+        .unwrap()
+        .json::<S>()
+        .await
+        .unwrap();
+
+    response
+}
+
+// for the few states the api can't reach on its own, like an expired token
+async fn execute_sql(query: &str) {
+    let config = config::Config::new().expect("Config can't be set");
+    let url = format!("postgres://{}:{}@{}:{}/{}", config.db_user, config.db_password, config.db_host, config.db_port, config.db_name);
+    let mut connection = PgConnection::connect(&url).await.unwrap();
+    sqlx::query(query).execute(&mut connection).await.unwrap();
+}
@@ -0,0 +1,101 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use chrono::Utc;
+use data_encoding::BASE64URL_NOPAD;
+use sha2::{Digest, Sha256};
+use warp::{hyper::StatusCode, Filter, Reply};
+
+// a bare OpenID Connect provider for the oidc login test: it logs in the
+// same user without asking and checks the PKCE verifier like a real one.
+// A `login_hint` added to the authorization url logs in another user, whose
+// email the provider didn't verify.
+pub const ISSUER: &str = "http://localhost:8090";
+pub const EMAIL: &str = "oidc@email.com";
+
+struct PendingCode {
+    client_id: String,
+    nonce: String,
+    code_challenge: String,
+    login_hint: Option<String>,
+}
+
+pub fn spawn() {
+    let codes: Arc<Mutex<HashMap<String, PendingCode>>> = Arc::default();
+
+    let discovery = warp::get()
+        .and(warp::path!(".well-known" / "openid-configuration"))
+        .map(|| {
+            warp::reply::json(&serde_json::json!({
+                "issuer": ISSUER,
+                "authorization_endpoint": format!("{}/authorize", ISSUER),
+                "token_endpoint": format!("{}/token", ISSUER),
+            }))
+        });
+
+    let authorize_codes = Arc::clone(&codes);
+    let authorize = warp::get()
+        .and(warp::path("authorize"))
+        .and(warp::query::<HashMap<String, String>>())
+        .map(move |query: HashMap<String, String>| {
+            let mut codes = authorize_codes.lock().unwrap();
+            let code = format!("code-{}", codes.len());
+            codes.insert(
+                code.clone(),
+                PendingCode {
+                    client_id: query["client_id"].clone(),
+                    nonce: query["nonce"].clone(),
+                    code_challenge: query["code_challenge"].clone(),
+                    login_hint: query.get("login_hint").cloned(),
+                },
+            );
+
+            let location = format!("{}?code={}&state={}", query["redirect_uri"], code, query["state"]);
+            warp::reply::with_header(StatusCode::FOUND, "location", location)
+        });
+
+    let token = warp::post()
+        .and(warp::path("token"))
+        .and(warp::body::form())
+        .map(move |form: HashMap<String, String>| {
+            let pending = match codes.lock().unwrap().remove(&form["code"]) {
+                Some(pending) => pending,
+                None => return StatusCode::BAD_REQUEST.into_response(),
+            };
+
+            let challenge = BASE64URL_NOPAD.encode(&Sha256::digest(form["code_verifier"].as_bytes()));
+            if challenge != pending.code_challenge {
+                return StatusCode::BAD_REQUEST.into_response();
+            }
+
+            let (subject, email, email_verified) = match pending.login_hint {
+                Some(hint) => (format!("mock-{}", hint), hint, false),
+                None => ("mock-subject".to_string(), EMAIL.to_string(), true),
+            };
+            let claims = serde_json::json!({
+                "iss": ISSUER,
+                "sub": subject,
+                "aud": pending.client_id,
+                "exp": Utc::now().timestamp() + 300,
+                "nonce": pending.nonce,
+                "email": email,
+                "email_verified": email_verified,
+            });
+            let id_token = format!(
+                "{}.{}.",
+                BASE64URL_NOPAD.encode(br#"{"alg":"none"}"#),
+                BASE64URL_NOPAD.encode(claims.to_string().as_bytes())
+            );
+
+            warp::reply::json(&serde_json::json!({
+                "access_token": "mock-access-token",
+                "token_type": "Bearer",
+                "id_token": id_token,
+            }))
+            .into_response()
+        });
+
+    tokio::spawn(warp::serve(discovery.or(authorize).or(token)).run(([127, 0, 0, 1], 8090)));
+}
@@ -0,0 +1,16 @@
+-- Add up migration script here
+CREATE TABLE IF NOT EXISTS refresh_tokens (
+    id serial PRIMARY KEY,
+    account_id int4 NOT NULL REFERENCES accounts ON DELETE CASCADE,
+    family_id VARCHAR (64) NOT NULL,
+    token_hash VARCHAR (64) NOT NULL,
+    expires_on TIMESTAMP NOT NULL,
+    used_on TIMESTAMP NULL,
+    revoked_on TIMESTAMP NULL,
+    created_on TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+ALTER TABLE refresh_tokens
+  ADD CONSTRAINT uq__refresh_tokens__token_hash UNIQUE (token_hash);
+
+CREATE INDEX IF NOT EXISTS ix__refresh_tokens__family_id ON refresh_tokens (family_id);
@@ -0,0 +1,8 @@
+-- Add up migration script here
+CREATE TABLE IF NOT EXISTS revoked_tokens (
+    jti VARCHAR (64) PRIMARY KEY,
+    expires_on TIMESTAMP WITH TIME ZONE NOT NULL,
+    created_on TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS ix__revoked_tokens__expires_on ON revoked_tokens (expires_on);
@@ -0,0 +1,3 @@
+-- Add down migration script here
+ALTER TABLE accounts DROP CONSTRAINT IF EXISTS ck__accounts__role;
+ALTER TABLE accounts DROP COLUMN IF EXISTS role;
\ No newline at end of file
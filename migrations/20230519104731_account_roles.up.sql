@@ -0,0 +1,6 @@
+-- Add up migration script here
+ALTER TABLE accounts
+  ADD COLUMN IF NOT EXISTS role VARCHAR (16) NOT NULL DEFAULT 'user';
+
+ALTER TABLE accounts
+  ADD CONSTRAINT ck__accounts__role CHECK (role IN ('user', 'moderator', 'admin'));
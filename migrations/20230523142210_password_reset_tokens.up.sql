@@ -0,0 +1,12 @@
+-- Add up migration script here
+CREATE TABLE IF NOT EXISTS password_reset_tokens (
+    id serial PRIMARY KEY,
+    account_id int4 NOT NULL REFERENCES accounts ON DELETE CASCADE,
+    token_hash VARCHAR (64) NOT NULL,
+    expires_on TIMESTAMP NOT NULL,
+    used_on TIMESTAMP NULL,
+    created_on TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+ALTER TABLE password_reset_tokens
+  ADD CONSTRAINT uq__password_reset_tokens__token_hash UNIQUE (token_hash);
@@ -0,0 +1,15 @@
+-- Add up migration script here
+ALTER TABLE accounts
+  ADD COLUMN IF NOT EXISTS verified_at TIMESTAMP NULL;
+
+CREATE TABLE IF NOT EXISTS email_verification_tokens (
+    id serial PRIMARY KEY,
+    account_id int4 NOT NULL REFERENCES accounts ON DELETE CASCADE,
+    token_hash VARCHAR (64) NOT NULL,
+    expires_on TIMESTAMP NOT NULL,
+    used_on TIMESTAMP NULL,
+    created_on TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+ALTER TABLE email_verification_tokens
+  ADD CONSTRAINT uq__email_verification_tokens__token_hash UNIQUE (token_hash);
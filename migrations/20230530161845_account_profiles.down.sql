@@ -0,0 +1,5 @@
+-- Add down migration script here
+ALTER TABLE accounts
+  DROP COLUMN IF EXISTS display_name,
+  DROP COLUMN IF EXISTS bio,
+  DROP COLUMN IF EXISTS avatar_url;
\ No newline at end of file
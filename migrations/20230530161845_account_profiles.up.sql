@@ -0,0 +1,5 @@
+-- Add up migration script here
+ALTER TABLE accounts
+  ADD COLUMN IF NOT EXISTS display_name VARCHAR (64) NULL,
+  ADD COLUMN IF NOT EXISTS bio TEXT NULL,
+  ADD COLUMN IF NOT EXISTS avatar_url VARCHAR (512) NULL;
@@ -0,0 +1,6 @@
+-- Add down migration script here
+DROP TABLE IF EXISTS recovery_codes;
+ALTER TABLE accounts
+  DROP COLUMN IF EXISTS totp_secret,
+  DROP COLUMN IF EXISTS totp_enabled_at,
+  DROP COLUMN IF EXISTS totp_last_step;
\ No newline at end of file
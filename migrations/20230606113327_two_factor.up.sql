@@ -0,0 +1,15 @@
+-- Add up migration script here
+ALTER TABLE accounts
+  ADD COLUMN IF NOT EXISTS totp_secret VARCHAR (64) NULL,
+  ADD COLUMN IF NOT EXISTS totp_enabled_at TIMESTAMP NULL,
+  ADD COLUMN IF NOT EXISTS totp_last_step BIGINT NULL;
+
+CREATE TABLE IF NOT EXISTS recovery_codes (
+    id serial PRIMARY KEY,
+    account_id int4 NOT NULL REFERENCES accounts ON DELETE CASCADE,
+    code_hash VARCHAR (64) NOT NULL,
+    used_on TIMESTAMP NULL,
+    created_on TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS ix__recovery_codes__account_id ON recovery_codes (account_id);
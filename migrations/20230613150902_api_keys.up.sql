@@ -0,0 +1,18 @@
+-- Add up migration script here
+CREATE TABLE IF NOT EXISTS api_keys (
+    id serial PRIMARY KEY,
+    account_id int4 NOT NULL REFERENCES accounts ON DELETE CASCADE,
+    name VARCHAR (64) NOT NULL,
+    prefix VARCHAR (16) NOT NULL,
+    key_hash VARCHAR (64) NOT NULL,
+    scopes TEXT [] NOT NULL,
+    expires_on TIMESTAMP WITH TIME ZONE NULL,
+    last_used_on TIMESTAMP WITH TIME ZONE NULL,
+    revoked_on TIMESTAMP WITH TIME ZONE NULL,
+    created_on TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+ALTER TABLE api_keys
+  ADD CONSTRAINT uq__api_keys__key_hash UNIQUE (key_hash);
+
+CREATE INDEX IF NOT EXISTS ix__api_keys__account_id ON api_keys (account_id);
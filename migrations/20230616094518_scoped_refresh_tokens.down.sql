@@ -0,0 +1,2 @@
+-- Add down migration script here
+ALTER TABLE refresh_tokens DROP COLUMN IF EXISTS scopes;
\ No newline at end of file
@@ -0,0 +1,4 @@
+-- Add up migration script here
+-- NULL for a regular login, which isn't restricted to any scope
+ALTER TABLE refresh_tokens
+  ADD COLUMN IF NOT EXISTS scopes TEXT [] NULL;
@@ -0,0 +1,3 @@
+-- Add down migration script here
+DROP TABLE IF EXISTS account_identities;
+DROP TABLE IF EXISTS oidc_logins;
\ No newline at end of file
@@ -0,0 +1,25 @@
+-- Add up migration script here
+-- a login started at /login/oidc, until the identity provider redirects back
+CREATE TABLE IF NOT EXISTS oidc_logins (
+    id serial PRIMARY KEY,
+    state_hash VARCHAR (64) NOT NULL,
+    code_verifier VARCHAR (128) NOT NULL,
+    nonce VARCHAR (64) NOT NULL,
+    expires_on TIMESTAMP NOT NULL,
+    created_on TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+ALTER TABLE oidc_logins
+  ADD CONSTRAINT uq__oidc_logins__state_hash UNIQUE (state_hash);
+
+-- external subjects linked to local accounts
+CREATE TABLE IF NOT EXISTS account_identities (
+    id serial PRIMARY KEY,
+    account_id int4 NOT NULL REFERENCES accounts ON DELETE CASCADE,
+    issuer VARCHAR (255) NOT NULL,
+    subject VARCHAR (255) NOT NULL,
+    created_on TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+ALTER TABLE account_identities
+  ADD CONSTRAINT uq__account_identities__issuer_subject UNIQUE (issuer, subject);
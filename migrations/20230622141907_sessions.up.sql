@@ -0,0 +1,16 @@
+-- Add up migration script here
+-- one row per login, i.e. per refresh token family. Deleting it signs the device out.
+CREATE TABLE IF NOT EXISTS sessions (
+    id serial PRIMARY KEY,
+    account_id int4 NOT NULL REFERENCES accounts ON DELETE CASCADE,
+    family_id VARCHAR (64) NOT NULL,
+    user_agent VARCHAR (512) NULL,
+    ip_address VARCHAR (45) NULL,
+    created_on TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    last_seen_on TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+ALTER TABLE sessions
+  ADD CONSTRAINT uq__sessions__family_id UNIQUE (family_id);
+
+CREATE INDEX IF NOT EXISTS ix__sessions__account_id ON sessions (account_id);
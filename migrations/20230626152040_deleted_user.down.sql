@@ -0,0 +1,4 @@
+-- Add down migration script here
+UPDATE questions SET account_id = NULL WHERE account_id = 0;
+UPDATE answers SET account_id = NULL WHERE account_id = 0;
+DELETE FROM accounts WHERE id = 0;
@@ -0,0 +1,12 @@
+-- Add up migration script here
+-- placeholder author for the questions and answers of deleted accounts.
+-- The password hashes a random value nobody knows, so it can't log in.
+INSERT INTO accounts (id, email, password, display_name, verified_at)
+VALUES (
+    0,
+    'deleted-user',
+    '$argon2i$v=19$m=4096,t=3,p=1$MZvRw3Hkf81X+Fe4caN4OVJuftoh1f8VtVlT0MBOOiI$bHN5KDYyCOMmyr8spWc7BH6FGq81r4EQRfrIiwH0jQ0',
+    'deleted user',
+    NOW()
+)
+ON CONFLICT DO NOTHING;
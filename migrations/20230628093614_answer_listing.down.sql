@@ -0,0 +1,5 @@
+-- Add down migration script here
+DROP INDEX IF EXISTS ix__answers__question_id;
+
+ALTER TABLE answers
+  DROP COLUMN IF EXISTS score;
@@ -0,0 +1,6 @@
+-- Add up migration script here
+-- kept up to date by voting, answers can already be sorted on it
+ALTER TABLE answers
+  ADD COLUMN IF NOT EXISTS score INTEGER NOT NULL DEFAULT 0;
+
+CREATE INDEX IF NOT EXISTS ix__answers__question_id ON answers (question_id);
@@ -0,0 +1,5 @@
+-- Add down migration script here
+ALTER TABLE answers
+  DROP CONSTRAINT IF EXISTS fk__answers__question_id,
+  ADD CONSTRAINT answers_question_id_fkey
+      FOREIGN KEY (question_id) REFERENCES questions;
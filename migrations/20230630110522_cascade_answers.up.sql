@@ -0,0 +1,6 @@
+-- Add up migration script here
+-- a question can't be deleted while answers point to it, they go with it instead
+ALTER TABLE answers
+  DROP CONSTRAINT IF EXISTS answers_question_id_fkey,
+  ADD CONSTRAINT fk__answers__question_id
+      FOREIGN KEY (question_id) REFERENCES questions ON DELETE CASCADE;
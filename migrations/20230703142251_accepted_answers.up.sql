@@ -0,0 +1,5 @@
+-- Add up migration script here
+-- the answer the question's author marked as solving it
+ALTER TABLE questions
+  ADD COLUMN IF NOT EXISTS accepted_answer_id int4 NULL
+      REFERENCES answers ON DELETE SET NULL;
@@ -0,0 +1,24 @@
+-- Add up migration script here
+-- one vote per account on either a question or an answer, +1 or -1.
+-- questions.score and answers.score hold the sums and are only written by voting.
+CREATE TABLE IF NOT EXISTS votes (
+    id serial PRIMARY KEY,
+    account_id int4 NOT NULL REFERENCES accounts ON DELETE CASCADE,
+    question_id int4 NULL REFERENCES questions ON DELETE CASCADE,
+    answer_id int4 NULL REFERENCES answers ON DELETE CASCADE,
+    value SMALLINT NOT NULL,
+    created_on TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    CONSTRAINT ck__votes__value CHECK (value IN (-1, 1)),
+    CONSTRAINT ck__votes__target CHECK ((question_id IS NULL) <> (answer_id IS NULL))
+);
+
+-- nulls are distinct, so a vote on an answer doesn't collide with one on a question
+ALTER TABLE votes
+  ADD CONSTRAINT uq__votes__account_id__question_id UNIQUE (account_id, question_id),
+  ADD CONSTRAINT uq__votes__account_id__answer_id UNIQUE (account_id, answer_id);
+
+CREATE INDEX IF NOT EXISTS ix__votes__question_id ON votes (question_id);
+CREATE INDEX IF NOT EXISTS ix__votes__answer_id ON votes (answer_id);
+
+ALTER TABLE questions
+  ADD COLUMN IF NOT EXISTS score INTEGER NOT NULL DEFAULT 0;
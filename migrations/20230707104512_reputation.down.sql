@@ -0,0 +1,3 @@
+-- Add down migration script here
+ALTER TABLE accounts DROP COLUMN IF EXISTS reputation;
+DROP TABLE IF EXISTS reputation_events;
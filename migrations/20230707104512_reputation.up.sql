@@ -0,0 +1,52 @@
+-- Add up migration script here
+-- every change to an account's reputation, never updated except by a replay.
+-- units is how many times the event counts, negative when it is taken back
+-- (a vote changed, an answer deleted...). points is units times what the
+-- event was worth when it was recorded, a replay prices it again.
+CREATE TABLE IF NOT EXISTS reputation_events (
+    id serial PRIMARY KEY,
+    account_id int4 NOT NULL REFERENCES accounts ON DELETE CASCADE,
+    kind VARCHAR (32) NOT NULL,
+    units INTEGER NOT NULL,
+    points INTEGER NOT NULL,
+    -- no foreign keys, the history outlives the posts
+    question_id int4 NULL,
+    answer_id int4 NULL,
+    created_on TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS ix__reputation_events__account_id ON reputation_events (account_id);
+CREATE INDEX IF NOT EXISTS ix__reputation_events__question_id ON reputation_events (question_id);
+CREATE INDEX IF NOT EXISTS ix__reputation_events__answer_id ON reputation_events (answer_id);
+
+-- sum of the account's points in the ledger
+ALTER TABLE accounts
+  ADD COLUMN IF NOT EXISTS reputation INTEGER NOT NULL DEFAULT 0;
+
+-- what was posted and voted before the ledger existed. It is worth nothing
+-- until a replay (POST /accounts/reputation/replay) prices it.
+INSERT INTO reputation_events (account_id, kind, units, points, question_id)
+SELECT account_id, 'question_asked', 1, 0, id
+  FROM questions
+ WHERE account_id IS NOT NULL;
+
+INSERT INTO reputation_events (account_id, kind, units, points, question_id, answer_id)
+SELECT account_id, 'answer_posted', 1, 0, question_id, id
+  FROM answers
+ WHERE account_id IS NOT NULL;
+
+INSERT INTO reputation_events (account_id, kind, units, points, question_id, answer_id)
+SELECT a.account_id, 'answer_accepted', 1, 0, q.id, a.id
+  FROM questions q
+  JOIN answers a ON a.id = q.accepted_answer_id
+ WHERE a.account_id IS NOT NULL
+   AND a.account_id IS DISTINCT FROM q.account_id;
+
+INSERT INTO reputation_events (account_id, kind, units, points, question_id, answer_id)
+SELECT COALESCE(q.account_id, a.account_id),
+       CASE WHEN v.value > 0 THEN 'upvoted' ELSE 'downvoted' END,
+       1, 0, COALESCE(v.question_id, a.question_id), v.answer_id
+  FROM votes v
+  LEFT JOIN questions q ON q.id = v.question_id
+  LEFT JOIN answers a ON a.id = v.answer_id
+ WHERE COALESCE(q.account_id, a.account_id) IS NOT NULL;
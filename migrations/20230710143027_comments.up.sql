@@ -0,0 +1,16 @@
+-- Add up migration script here
+-- short remarks on a question or an answer, they go with what they are on
+CREATE TABLE IF NOT EXISTS comments (
+    id serial PRIMARY KEY,
+    content VARCHAR (600) NOT NULL,
+    account_id int4 REFERENCES accounts,
+    question_id int4 NULL REFERENCES questions ON DELETE CASCADE,
+    answer_id int4 NULL REFERENCES answers ON DELETE CASCADE,
+    created_on TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_on TIMESTAMP WITH TIME ZONE NULL,
+    CONSTRAINT ck__comments__target CHECK ((question_id IS NULL) <> (answer_id IS NULL))
+);
+
+CREATE INDEX IF NOT EXISTS ix__comments__question_id ON comments (question_id);
+CREATE INDEX IF NOT EXISTS ix__comments__answer_id ON comments (answer_id);
+CREATE INDEX IF NOT EXISTS ix__comments__account_id ON comments (account_id);
@@ -0,0 +1,12 @@
+-- Add up migration script here
+-- a challenge token handed out by /login, until /login/2fa uses it
+CREATE TABLE IF NOT EXISTS login_challenges (
+    id serial PRIMARY KEY,
+    jti VARCHAR (64) NOT NULL,
+    account_id int4 NOT NULL REFERENCES accounts ON DELETE CASCADE,
+    expires_on TIMESTAMP NOT NULL,
+    created_on TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+ALTER TABLE login_challenges
+  ADD CONSTRAINT uq__login_challenges__jti UNIQUE (jti);
@@ -0,0 +1,3 @@
+-- Add down migration script here
+ALTER TABLE sessions DROP COLUMN IF EXISTS login_method;
+ALTER TABLE accounts DROP COLUMN IF EXISTS password_set;
@@ -0,0 +1,9 @@
+-- Add up migration script here
+-- false for accounts created by an OpenID Connect login, whose random password
+-- nobody knows, until a password is set with a reset.
+ALTER TABLE accounts
+  ADD COLUMN IF NOT EXISTS password_set BOOLEAN NOT NULL DEFAULT TRUE;
+
+-- how a session was logged into: password, oidc or scoped (from another session)
+ALTER TABLE sessions
+  ADD COLUMN IF NOT EXISTS login_method VARCHAR (16) NOT NULL DEFAULT 'password';
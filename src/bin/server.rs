@@ -4,7 +4,13 @@ use qa_api::config::Config;
 pub async fn main() -> Result<(), warp::Rejection>{
     dotenv::dotenv().ok();
 
-    let config = Config::new().expect("Config can't be set");
+    let config = match Config::new() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Config can't be set: {}", e);
+            std::process::exit(1);
+        }
+    };
 
     let store = qa_api::setup_repository(&config).await?;
 
@@ -1,4 +1,171 @@
-use std::{fmt::Error, env, num::ParseIntError};
+use std::{env, fmt::Display, num::ParseIntError};
+
+use crate::models::reputation::ReputationEvent;
+
+// paseto v2.local keys, ed25519 seeds and public keys are all this long
+const PASETO_KEY_LENGTH: usize = 32;
+
+#[derive(Debug)]
+pub enum ConfigError {
+    MissingPasetoKey,
+    // the id of the key which is not 32 bytes long
+    InvalidPasetoKey(String),
+    InvalidRetiredPasetoKeys,
+    DuplicatePasetoKeyId(String),
+    MissingSigningKey,
+    InvalidTokenMode(String),
+    InvalidArgon2Params,
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::MissingPasetoKey => {
+                write!(f, "please define the PASETO_KEY env variable")
+            },
+            ConfigError::InvalidPasetoKey(id) => {
+                write!(f, "paseto key '{}' must be exactly {} bytes long", id, PASETO_KEY_LENGTH)
+            },
+            ConfigError::InvalidRetiredPasetoKeys => {
+                write!(f, "retired paseto keys must be a comma separated list of id:key")
+            },
+            ConfigError::DuplicatePasetoKeyId(id) => {
+                write!(f, "paseto key id '{}' is used more than once", id)
+            },
+            ConfigError::MissingSigningKey => {
+                write!(f, "PASETO_MODE=public needs the PASETO_SIGNING_KEY env variable")
+            },
+            ConfigError::InvalidTokenMode(mode) => {
+                write!(f, "PASETO_MODE must be local or public, not '{}'", mode)
+            },
+            ConfigError::InvalidArgon2Params => {
+                write!(f, "ARGON2_VARIANT must be argon2i, argon2d or argon2id, ARGON2_PARALLELISM \
+                    and ARGON2_ITERATIONS between 1 and {}, and ARGON2_MEMORY_KIB between 8 per lane \
+                    and {}", ARGON2_MAX_COST, ARGON2_MAX_MEMORY_KIB)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+// a token key and the id written in the footer of the tokens it encrypts
+#[derive(Clone)]
+pub struct PasetoKey {
+    pub id: String,
+    pub key: Vec<u8>,
+}
+
+impl PasetoKey {
+    fn new(id: &str, key: &str) -> Result<Self, ConfigError> {
+        Self::from_bytes(id, key.as_bytes().to_vec())
+    }
+
+    // ed25519 keys are binary, so they are configured hex encoded
+    fn from_hex(id: &str, key: &str) -> Result<Self, ConfigError> {
+        let key = hex::decode(key.trim())
+            .map_err(|_| ConfigError::InvalidPasetoKey(id.to_string()))?;
+        Self::from_bytes(id, key)
+    }
+
+    fn from_bytes(id: &str, key: Vec<u8>) -> Result<Self, ConfigError> {
+        if key.len() != PASETO_KEY_LENGTH {
+            return Err(ConfigError::InvalidPasetoKey(id.to_string()));
+        }
+
+        Ok(PasetoKey {
+            id: id.to_string(),
+            key,
+        })
+    }
+}
+
+// keeps the key itself out of logs
+impl std::fmt::Debug for PasetoKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PasetoKey").field("id", &self.id).finish_non_exhaustive()
+    }
+}
+
+// a single hash shouldn't take more than a fraction of the server's memory
+// or minutes of cpu time, higher values are most likely a typo.
+const ARGON2_MAX_MEMORY_KIB: u32 = 1024 * 1024;
+const ARGON2_MAX_COST: u32 = 64;
+
+// cost of new password hashes. Raising it makes logins rehash passwords
+// stored with weaker parameters, see `AuthenticationController::login`.
+#[derive(Debug, Clone, Copy)]
+pub struct Argon2Params {
+    pub variant: argon2::Variant,
+    pub mem_cost: u32,
+    pub time_cost: u32,
+    pub lanes: u32,
+}
+
+impl Argon2Params {
+    fn new(variant: &str, mem_cost: &str, time_cost: &str, lanes: &str) -> Result<Self, ConfigError> {
+        let params = Argon2Params {
+            variant: argon2::Variant::from_str(variant).map_err(|_| ConfigError::InvalidArgon2Params)?,
+            mem_cost: mem_cost.parse().map_err(|_| ConfigError::InvalidArgon2Params)?,
+            time_cost: time_cost.parse().map_err(|_| ConfigError::InvalidArgon2Params)?,
+            lanes: lanes.parse().map_err(|_| ConfigError::InvalidArgon2Params)?,
+        };
+
+        // argon2 would refuse to hash with these, better to find out at startup
+        if !(1..=ARGON2_MAX_COST).contains(&params.lanes)
+            || !(1..=ARGON2_MAX_COST).contains(&params.time_cost)
+            || !(8 * params.lanes..=ARGON2_MAX_MEMORY_KIB).contains(&params.mem_cost)
+        {
+            return Err(ConfigError::InvalidArgon2Params);
+        }
+
+        Ok(params)
+    }
+}
+
+// reputation earned per event, negative values take reputation away.
+// Changing them only affects new events until the ledger is replayed.
+#[derive(Debug, Clone, Copy)]
+pub struct ReputationPoints {
+    pub question_asked: i32,
+    pub answer_posted: i32,
+    pub upvoted: i32,
+    pub downvoted: i32,
+    pub answer_accepted: i32,
+}
+
+impl ReputationPoints {
+    pub fn of(&self, event: ReputationEvent) -> i32 {
+        match event {
+            ReputationEvent::QuestionAsked => self.question_asked,
+            ReputationEvent::AnswerPosted => self.answer_posted,
+            ReputationEvent::Upvoted => self.upvoted,
+            ReputationEvent::Downvoted => self.downvoted,
+            ReputationEvent::AnswerAccepted => self.answer_accepted,
+        }
+    }
+}
+
+pub enum MailerKind {
+    Stdout,
+    File,
+}
+
+// what happens to the questions and answers of a deleted account. Anonymized
+// content stays up, credited to the "deleted user" placeholder account.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccountDeletion {
+    Anonymize,
+    HardDelete,
+}
+
+// local tokens are encrypted with a shared secret. Public tokens are signed
+// and can be checked by other services with the keys at /.well-known/paseto-keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenMode {
+    Local,
+    Public,
+}
 
 pub struct Config {
     pub api_port: u16,
@@ -7,10 +174,48 @@ pub struct Config {
     pub db_host: String,
     pub db_port: u16,
     pub db_name: String,
+    pub access_token_minutes: i64,
+    pub refresh_token_days: i64,
+    pub password_reset_minutes: i64,
+    pub mailer: MailerKind,
+    pub mail_drop_dir: String,
+    // base url used in links sent by email
+    pub public_url: String,
+    pub email_verification_hours: i64,
+    pub require_verified_email: bool,
+    pub login_max_attempts: u32,
+    pub login_lockout_seconds: i64,
+    pub login_lockout_max_seconds: i64,
+    // only enable behind a proxy that sets the header, clients can forge it
+    pub trust_forwarded_for: bool,
+    // shown by authenticator apps next to the account
+    pub totp_issuer: String,
+    // encrypts new tokens
+    pub paseto_key: PasetoKey,
+    // only decrypt tokens issued before a rotation, until they expire
+    pub retired_paseto_keys: Vec<PasetoKey>,
+    pub token_mode: TokenMode,
+    // ed25519 seed signing session tokens in public mode
+    pub paseto_signing_key: Option<PasetoKey>,
+    // still published and accepted after the signing key was rotated
+    pub retired_paseto_public_keys: Vec<PasetoKey>,
+    // OpenID Connect login is off unless an issuer is set
+    pub oidc_issuer: Option<String>,
+    pub oidc_client_id: String,
+    pub oidc_client_secret: Option<String>,
+    pub oidc_redirect_url: String,
+    pub account_deletion: AccountDeletion,
+    pub argon2: Argon2Params,
+    // password hashes computed at once, and how many more may wait
+    pub hashing_workers: usize,
+    pub hashing_queue: usize,
+    pub reputation_points: ReputationPoints,
+    // needed to edit questions and answers of other accounts, moderators always can
+    pub reputation_to_edit: i32,
 }
 
 impl Config {
-    pub fn new() -> Result<Self, Error> {
+    pub fn new() -> Result<Self, ConfigError> {
 
         let api_port = env::var("PORT").unwrap();
         let db_user = env::var("POSTGRES_USER").unwrap();
@@ -18,6 +223,65 @@ impl Config {
         let db_host = env::var("POSTGRES_HOST").unwrap();
         let db_port = env::var("POSTGRES_PORT").unwrap();
         let db_name = env::var("POSTGRES_DB").unwrap();
+        let access_token_minutes = env::var("ACCESS_TOKEN_MINUTES").unwrap_or_else(|_| "15".to_string());
+        let refresh_token_days = env::var("REFRESH_TOKEN_DAYS").unwrap_or_else(|_| "30".to_string());
+        let password_reset_minutes = env::var("PASSWORD_RESET_MINUTES").unwrap_or_else(|_| "30".to_string());
+        let mailer = env::var("MAILER").unwrap_or_else(|_| "stdout".to_string());
+        let mail_drop_dir = env::var("MAIL_DROP_DIR").unwrap_or_else(|_| "mail".to_string());
+        let public_url = env::var("PUBLIC_URL").unwrap_or_else(|_| format!("http://localhost:{}", api_port));
+        let email_verification_hours = env::var("EMAIL_VERIFICATION_HOURS").unwrap_or_else(|_| "48".to_string());
+        let require_verified_email = env::var("REQUIRE_VERIFIED_EMAIL").unwrap_or_else(|_| "false".to_string());
+        let login_max_attempts = env::var("LOGIN_MAX_ATTEMPTS").unwrap_or_else(|_| "5".to_string());
+        let login_lockout_seconds = env::var("LOGIN_LOCKOUT_SECONDS").unwrap_or_else(|_| "30".to_string());
+        let login_lockout_max_seconds = env::var("LOGIN_LOCKOUT_MAX_SECONDS").unwrap_or_else(|_| "900".to_string());
+        let trust_forwarded_for = env::var("TRUST_X_FORWARDED_FOR").unwrap_or_else(|_| "false".to_string());
+        let totp_issuer = env::var("TOTP_ISSUER").unwrap_or_else(|_| "qa-api".to_string());
+        let paseto_key = env::var("PASETO_KEY").map_err(|_| ConfigError::MissingPasetoKey)?;
+        let paseto_key_id = env::var("PASETO_KEY_ID").unwrap_or_else(|_| "1".to_string());
+        let retired_paseto_keys = env::var("PASETO_RETIRED_KEYS").unwrap_or_default();
+
+        let token_mode = env::var("PASETO_MODE").unwrap_or_else(|_| "local".to_string());
+        let paseto_signing_key = env::var("PASETO_SIGNING_KEY").ok().filter(|key| !key.is_empty());
+        let paseto_signing_key_id = env::var("PASETO_SIGNING_KEY_ID").unwrap_or_else(|_| "1".to_string());
+        let retired_paseto_public_keys = env::var("PASETO_RETIRED_PUBLIC_KEYS").unwrap_or_default();
+
+        let oidc_issuer = env::var("OIDC_ISSUER").ok().filter(|issuer| !issuer.is_empty());
+        let oidc_client_id = env::var("OIDC_CLIENT_ID").unwrap_or_else(|_| "qa-api".to_string());
+        let oidc_client_secret = env::var("OIDC_CLIENT_SECRET").ok().filter(|secret| !secret.is_empty());
+        let oidc_redirect_url = env::var("OIDC_REDIRECT_URL").unwrap_or_else(|_| format!("{}/login/oidc/callback", public_url));
+        let account_deletion = env::var("ACCOUNT_DELETION").unwrap_or_else(|_| "anonymize".to_string());
+
+        let argon2_variant = env::var("ARGON2_VARIANT").unwrap_or_else(|_| "argon2id".to_string());
+        let argon2_memory_kib = env::var("ARGON2_MEMORY_KIB").unwrap_or_else(|_| "19456".to_string());
+        let argon2_iterations = env::var("ARGON2_ITERATIONS").unwrap_or_else(|_| "2".to_string());
+        let argon2_parallelism = env::var("ARGON2_PARALLELISM").unwrap_or_else(|_| "1".to_string());
+        let hashing_workers = env::var("HASHING_WORKERS").ok().filter(|workers| !workers.is_empty());
+        let hashing_queue = env::var("HASHING_QUEUE").unwrap_or_else(|_| "32".to_string());
+        let reputation_question_asked = env::var("REPUTATION_QUESTION_ASKED").unwrap_or_else(|_| "1".to_string());
+        let reputation_answer_posted = env::var("REPUTATION_ANSWER_POSTED").unwrap_or_else(|_| "2".to_string());
+        let reputation_upvoted = env::var("REPUTATION_UPVOTED").unwrap_or_else(|_| "10".to_string());
+        let reputation_downvoted = env::var("REPUTATION_DOWNVOTED").unwrap_or_else(|_| "-2".to_string());
+        let reputation_answer_accepted = env::var("REPUTATION_ANSWER_ACCEPTED").unwrap_or_else(|_| "15".to_string());
+        let reputation_to_edit = env::var("REPUTATION_TO_EDIT").unwrap_or_else(|_| "2000".to_string());
+        let argon2 = Argon2Params::new(&argon2_variant, &argon2_memory_kib, &argon2_iterations, &argon2_parallelism)?;
+
+        let paseto_key = PasetoKey::new(&paseto_key_id, &paseto_key)?;
+        let retired_paseto_keys = parse_key_list(&retired_paseto_keys, PasetoKey::new)?;
+        check_unique_ids(std::iter::once(&paseto_key).chain(&retired_paseto_keys))?;
+
+        // a typo must not quietly fall back to local tokens
+        let token_mode = match token_mode.as_str() {
+            "local" => TokenMode::Local,
+            "public" => TokenMode::Public,
+            _ => return Err(ConfigError::InvalidTokenMode(token_mode)),
+        };
+        let paseto_signing_key = match (token_mode, paseto_signing_key) {
+            (TokenMode::Public, None) => return Err(ConfigError::MissingSigningKey),
+            (_, Some(seed)) => Some(PasetoKey::from_hex(&paseto_signing_key_id, &seed)?),
+            (_, None) => None,
+        };
+        let retired_paseto_public_keys = parse_key_list(&retired_paseto_public_keys, PasetoKey::from_hex)?;
+        check_unique_ids(paseto_signing_key.iter().chain(&retired_paseto_public_keys))?;
 
         Ok(Config {
             api_port: api_port
@@ -30,6 +294,107 @@ impl Config {
                 .parse::<u16>()
                 .map_err(|e| -> Result<u16, ParseIntError> { Err(e) }).unwrap(),
             db_name,
+            access_token_minutes: access_token_minutes
+                .parse::<i64>()
+                .map_err(|e| -> Result<i64, ParseIntError> { Err(e) }).unwrap(),
+            refresh_token_days: refresh_token_days
+                .parse::<i64>()
+                .map_err(|e| -> Result<i64, ParseIntError> { Err(e) }).unwrap(),
+            password_reset_minutes: password_reset_minutes
+                .parse::<i64>()
+                .map_err(|e| -> Result<i64, ParseIntError> { Err(e) }).unwrap(),
+            mailer: match mailer.as_str() {
+                "file" => MailerKind::File,
+                _ => MailerKind::Stdout,
+            },
+            mail_drop_dir,
+            public_url,
+            email_verification_hours: email_verification_hours
+                .parse::<i64>()
+                .map_err(|e| -> Result<i64, ParseIntError> { Err(e) }).unwrap(),
+            require_verified_email: require_verified_email == "true",
+            login_max_attempts: login_max_attempts
+                .parse::<u32>()
+                .map_err(|e| -> Result<u32, ParseIntError> { Err(e) }).unwrap(),
+            login_lockout_seconds: login_lockout_seconds
+                .parse::<i64>()
+                .map_err(|e| -> Result<i64, ParseIntError> { Err(e) }).unwrap(),
+            login_lockout_max_seconds: login_lockout_max_seconds
+                .parse::<i64>()
+                .map_err(|e| -> Result<i64, ParseIntError> { Err(e) }).unwrap(),
+            trust_forwarded_for: trust_forwarded_for == "true",
+            totp_issuer,
+            paseto_key,
+            retired_paseto_keys,
+            token_mode,
+            paseto_signing_key,
+            retired_paseto_public_keys,
+            oidc_issuer,
+            oidc_client_id,
+            oidc_client_secret,
+            oidc_redirect_url,
+            account_deletion: match account_deletion.as_str() {
+                "delete" => AccountDeletion::HardDelete,
+                _ => AccountDeletion::Anonymize,
+            },
+            argon2,
+            // one per core by default, hashing is cpu bound
+            hashing_workers: match hashing_workers {
+                Some(workers) => workers
+                    .parse::<usize>()
+                    .map_err(|e| -> Result<usize, ParseIntError> { Err(e) }).unwrap(),
+                None => std::thread::available_parallelism().map_or(1, |cores| cores.get()),
+            },
+            hashing_queue: hashing_queue
+                .parse::<usize>()
+                .map_err(|e| -> Result<usize, ParseIntError> { Err(e) }).unwrap(),
+            reputation_points: ReputationPoints {
+                question_asked: reputation_question_asked
+                    .parse::<i32>()
+                    .map_err(|e| -> Result<i32, ParseIntError> { Err(e) }).unwrap(),
+                answer_posted: reputation_answer_posted
+                    .parse::<i32>()
+                    .map_err(|e| -> Result<i32, ParseIntError> { Err(e) }).unwrap(),
+                upvoted: reputation_upvoted
+                    .parse::<i32>()
+                    .map_err(|e| -> Result<i32, ParseIntError> { Err(e) }).unwrap(),
+                downvoted: reputation_downvoted
+                    .parse::<i32>()
+                    .map_err(|e| -> Result<i32, ParseIntError> { Err(e) }).unwrap(),
+                answer_accepted: reputation_answer_accepted
+                    .parse::<i32>()
+                    .map_err(|e| -> Result<i32, ParseIntError> { Err(e) }).unwrap(),
+            },
+            reputation_to_edit: reputation_to_edit
+                .parse::<i32>()
+                .map_err(|e| -> Result<i32, ParseIntError> { Err(e) }).unwrap(),
         })
     }
 }
+
+// `id:key,id:key`. Keys can hold a colon but not a comma.
+fn parse_key_list(
+    keys: &str,
+    parse: fn(&str, &str) -> Result<PasetoKey, ConfigError>,
+) -> Result<Vec<PasetoKey>, ConfigError> {
+    keys.split(',')
+        .filter(|entry| !entry.trim().is_empty())
+        .map(|entry| match entry.split_once(':') {
+            Some((id, key)) if !id.trim().is_empty() => parse(id.trim(), key),
+            _ => Err(ConfigError::InvalidRetiredPasetoKeys),
+        })
+        .collect()
+}
+
+// a token footer must point to a single key
+fn check_unique_ids<'a>(keys: impl Iterator<Item = &'a PasetoKey>) -> Result<(), ConfigError> {
+    let mut key_ids: Vec<&str> = Vec::new();
+    for key in keys {
+        if key_ids.contains(&key.id.as_str()) {
+            return Err(ConfigError::DuplicatePasetoKeyId(key.id.clone()));
+        }
+        key_ids.push(&key.id);
+    }
+
+    Ok(())
+}
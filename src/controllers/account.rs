@@ -0,0 +1,156 @@
+use std::{collections::HashMap, sync::Arc};
+
+use chrono::Utc;
+use warp::{
+    hyper::StatusCode,
+    reject::custom,
+    reply::{json, with_status},
+    Future, Rejection, Reply,
+};
+
+use crate::{
+    config::{Config, ReputationPoints},
+    custom_errors::account,
+    models::{
+        account::{AccountId, RoleUpdate, Session},
+        profile::{AccountExport, ProfileUpdate},
+        pagination::{extract_pagination, Pagination},
+    },
+    repository::{database_repository::DatabaseRepository, Repository},
+};
+
+// profiles, plus account management for admins.
+pub struct AccountController {
+    pub repository: Arc<Repository>,
+    reputation_points: ReputationPoints,
+}
+
+impl AccountController {
+    pub fn new(store: Arc<Repository>, config: &Config) -> Self {
+        Self {
+            repository: store,
+            reputation_points: config.reputation_points,
+        }
+    }
+
+    pub fn get_accounts(
+        &self,
+        params: HashMap<String, String>,
+    ) -> impl Future<Output = Result<impl Reply, Rejection>> + Send + '_ {
+        async move {
+            let mut pagination = Pagination::default();
+
+            if !params.is_empty() {
+                pagination = extract_pagination(params)?;
+            }
+
+            let result = self
+                .repository
+                .get_accounts(pagination.limit, pagination.offset)
+                .await;
+            match result {
+                Ok(accounts) => Ok(json(&accounts)),
+                Err(e) => Err(custom(e)),
+            }
+        }
+    }
+
+    pub fn update_role(
+        &self,
+        account_id: AccountId,
+        update: RoleUpdate,
+    ) -> impl Future<Output = Result<impl Reply, Rejection>> + Send + '_ {
+        async move {
+            let result = self
+                .repository
+                .update_account_role(account_id, update.role)
+                .await;
+            match result {
+                Ok(account) => Ok(json(&account)),
+                Err(e) => Err(custom(e)),
+            }
+        }
+    }
+
+    // after the points were reconfigured, or to repair the totals
+    pub fn replay_reputation(&self) -> impl Future<Output = Result<impl Reply, Rejection>> + Send + '_ {
+        async move {
+            match self.repository.replay_reputation(self.reputation_points).await {
+                Ok(_) => Ok(with_status("reputation recomputed", StatusCode::OK)),
+                Err(e) => Err(custom(e)),
+            }
+        }
+    }
+
+    pub fn get_own_profile(
+        &self,
+        session: Session,
+    ) -> impl Future<Output = Result<impl Reply, Rejection>> + Send + '_ {
+        async move {
+            match self.repository.get_profile(session.account_id).await {
+                Ok(profile) => Ok(json(&profile)),
+                Err(e) => Err(custom(e)),
+            }
+        }
+    }
+
+    pub fn update_own_profile(
+        &self,
+        session: Session,
+        profile: ProfileUpdate,
+    ) -> impl Future<Output = Result<impl Reply, Rejection>> + Send + '_ {
+        async move {
+            if !profile.is_valid() {
+                return Err(custom(account::Error::InvalidProfile));
+            }
+
+            match self
+                .repository
+                .update_profile(session.account_id, profile)
+                .await
+            {
+                Ok(profile) => Ok(json(&profile)),
+                Err(e) => Err(custom(e)),
+            }
+        }
+    }
+
+    // a copy of the caller's data in one json document
+    pub fn export_account(
+        &self,
+        session: Session,
+    ) -> impl Future<Output = Result<impl Reply, Rejection>> + Send + '_ {
+        async move {
+            let account = self.repository.get_profile(session.account_id.clone()).await?;
+            let questions = self
+                .repository
+                .get_account_questions(session.account_id.clone())
+                .await?;
+            let answers = self
+                .repository
+                .get_account_answers(session.account_id.clone())
+                .await?;
+            let comments = self.repository.get_account_comments(session.account_id).await?;
+
+            Ok(json(&AccountExport {
+                account,
+                questions,
+                answers,
+                comments,
+                exported_on: Utc::now(),
+            }))
+        }
+    }
+
+    pub fn get_public_profile(
+        &self,
+        account_id: AccountId,
+    ) -> impl Future<Output = Result<impl Reply, Rejection>> + Send + '_ {
+        async move {
+            match self.repository.get_public_profile(account_id).await {
+                Ok(profile) => Ok(json(&profile)),
+                Err(e) => Err(custom(e)),
+            }
+        }
+    }
+}
@@ -1,23 +1,41 @@
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
-use warp::{reject::custom, reply::json, Future, Rejection, Reply};
+use warp::{
+    hyper::StatusCode,
+    reject::custom,
+    reply::{json, with_status},
+    Future, Rejection, Reply,
+};
 
 use crate::{
-    custom_errors::account,
+    config::{Config, ReputationPoints},
+    custom_errors::{account, common},
     models::{
         account::Session,
-        answer::{AnswerDTO, AnswerId},
+        answer::{AnswerDTO, AnswerId, AnswerSort},
+        pagination::{extract_pagination, Pagination},
+        post::PostId,
+        question::QuestionId,
+        vote::{Score, Vote},
     },
     repository::{database_repository::DatabaseRepository, Repository},
 };
 
 pub struct AnswerController {
     pub repository: Arc<Repository>,
+    require_verified_email: bool,
+    reputation_points: ReputationPoints,
+    reputation_to_edit: i32,
 }
 
 impl AnswerController {
-    pub fn new(store: Arc<Repository>) -> Self {
-        Self { repository: store }
+    pub fn new(store: Arc<Repository>, config: &Config) -> Self {
+        Self {
+            repository: store,
+            require_verified_email: config.require_verified_email,
+            reputation_points: config.reputation_points,
+            reputation_to_edit: config.reputation_to_edit,
+        }
     }
 
     pub fn create_answer(
@@ -26,9 +44,18 @@ impl AnswerController {
         answer: AnswerDTO,
     ) -> impl Future<Output = Result<impl Reply, Rejection>> + Send + '_ {
         async move {
+            if self.require_verified_email
+                && !self
+                    .repository
+                    .is_account_verified(session.account_id.clone())
+                    .await?
+            {
+                return Err(custom(account::Error::EmailNotVerified));
+            }
+
             let result = self
                 .repository
-                .create_answer(answer, session.account_id)
+                .create_answer(answer, session.account_id, self.reputation_points)
                 .await;
             match result {
                 Ok(answer) => Ok(json(&answer)),
@@ -37,6 +64,49 @@ impl AnswerController {
         }
     }
 
+    pub fn get_answer(
+        &self,
+        answer_id: AnswerId,
+    ) -> impl Future<Output = Result<impl Reply, Rejection>> + Send + '_ {
+        async move {
+            match self.repository.get_answer(answer_id).await {
+                Ok(answer) => Ok(json(&answer)),
+                Err(e) => Err(custom(e)),
+            }
+        }
+    }
+
+    // `sort` is newest, oldest or score, and pages work as for questions
+    pub fn get_answers(
+        &self,
+        question_id: QuestionId,
+        mut params: HashMap<String, String>,
+    ) -> impl Future<Output = Result<impl Reply, Rejection>> + Send + '_ {
+        async move {
+            let sort = match params.remove("sort") {
+                Some(sort) => AnswerSort::parse(&sort).ok_or(common::Error::InvalidSort)?,
+                None => AnswerSort::default(),
+            };
+
+            let mut pagination = Pagination::default();
+            if !params.is_empty() {
+                pagination = extract_pagination(params)?;
+            }
+
+            // an unknown question is a 404, not an empty list
+            self.repository.get_question(question_id).await?;
+
+            let result = self
+                .repository
+                .get_answers(question_id, sort, pagination.limit, pagination.offset)
+                .await;
+            match result {
+                Ok(answers) => Ok(json(&answers)),
+                Err(e) => Err(custom(e)),
+            }
+        }
+    }
+
     pub fn update_answer(
         &self,
         session: Session,
@@ -44,15 +114,45 @@ impl AnswerController {
         answer: AnswerDTO,
     ) -> impl Future<Output = Result<impl Reply, Rejection>> + Send + '_ {
         async move {
-            if self
-                .repository
-                .is_answer_owner(answer_id, session.account_id)
-                .await?
+            let existing = self.repository.get_answer(answer_id).await?;
+            let is_owner = existing
+                .author
+                .is_some_and(|author| author.id.0 == session.account_id.0);
+
+            // same rules as for questions
+            if !session.role.can_moderate()
+                && !is_owner
+                && self.repository.get_reputation(session.account_id).await? < self.reputation_to_edit
             {
-                let result = self.repository.update_answer(answer, answer_id).await;
+                return Err(custom(account::Error::InsufficientReputation(self.reputation_to_edit)));
+            }
+
+            let result = self.repository.update_answer(answer, answer_id).await;
+
+            match result {
+                Ok(answer) => Ok(json(&answer)),
+                Err(e) => Err(custom(e)),
+            }
+        }
+    }
 
-                match result {
-                    Ok(answer) => Ok(json(&answer)),
+    pub fn delete_answer(
+        &self,
+        session: Session,
+        answer_id: AnswerId,
+    ) -> impl Future<Output = Result<impl Reply, Rejection>> + Send + '_ {
+        async move {
+            if session.role.can_moderate()
+                || self
+                    .repository
+                    .is_answer_owner(answer_id, session.account_id)
+                    .await?
+            {
+                match self.repository.delete_answer(answer_id).await {
+                    Ok(_) => Ok(with_status(
+                        format!("answer id: {} deleted", answer_id.0),
+                        StatusCode::OK,
+                    )),
                     Err(e) => Err(custom(e)),
                 }
             } else {
@@ -60,4 +160,31 @@ impl AnswerController {
             }
         }
     }
+
+    // None takes the vote back
+    pub fn vote_answer(
+        &self,
+        session: Session,
+        answer_id: AnswerId,
+        vote: Option<Vote>,
+    ) -> impl Future<Output = Result<impl Reply, Rejection>> + Send + '_ {
+        async move {
+            if self
+                .repository
+                .is_answer_owner(answer_id, session.account_id.clone())
+                .await?
+            {
+                return Err(custom(common::Error::OwnContentVote));
+            }
+
+            let result = self
+                .repository
+                .cast_vote(session.account_id, PostId::Answer(answer_id), vote, self.reputation_points)
+                .await;
+            match result {
+                Ok(score) => Ok(json(&Score { score })),
+                Err(e) => Err(custom(e)),
+            }
+        }
+    }
 }
@@ -1,26 +1,113 @@
-use argon2::Config;
 use chrono::Utc;
 use paseto::PasetoBuilder;
 use rand::Rng;
-use std::{env, future, sync::Arc};
+use std::{
+    future,
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+};
 use warp::{
-    header, hyper::StatusCode, reject::custom, reply::json, Filter, Future, Rejection, Reply,
+    addr, header, hyper::StatusCode, reject::custom, reply::json, Filter, Future, Rejection,
+    Reply,
 };
 
 use crate::{
-    custom_errors::account::Error,
-    models::account::{Account, AccountId, Session},
+    config,
+    crypto::{generate_token, hash_token},
+    custom_errors::{
+        account::Error, oidc::Error as OidcError, repository::Error as RepositoryError,
+    },
+    hasher::{hash_password, PasswordHasher},
+    keyring::Keyring,
+    mailer::{Mail, Mailer},
+    oidc::OidcClient,
+    models::{
+        api_key::{
+            ApiKeyId, ApiKeyRequest, CreatedApiKey, API_KEY_MAX_EXPIRY_DAYS, API_KEY_NAME_MAX_LENGTH,
+        },
+        oidc::{OidcCallback, OidcLogin},
+        account::{
+            is_valid_email, Account, AccountDeletionRequest, AccountId, EmailVerification, LoginRequest,
+            NewAccount, PasswordChange, PasswordHash, PasswordResetConfirmation,
+            PasswordResetRequest, RegistrationRequest, Role, Session, VerificationResendRequest,
+        },
+        scope::Scope,
+        token::{ClientInfo, LoginMethod, RefreshRequest, ScopedTokenRequest, TokenPair},
+        two_factor::{
+            ChallengeResponse, LoginChallenge, RecoveryCodes, TwoFactorChallenge, TwoFactorCode,
+            TwoFactorEnrollment, CHALLENGE_PURPOSE,
+        },
+    },
     repository::{database_repository::DatabaseRepository, Repository},
+    revocation::RevocationStore,
+    throttle::LoginThrottle,
+    totp,
 };
 
+// how long the second login step can take
+const CHALLENGE_TTL_MINUTES: i64 = 5;
+const RECOVERY_CODES: usize = 10;
+// how long the identity provider has to redirect back
+const OIDC_LOGIN_TTL_MINUTES: i64 = 10;
+// how old an oidc login can be to stand in for the password, see `delete_account`
+const RECENT_LOGIN_MINUTES: i64 = 10;
+// holds the state in the browser that started an oidc login
+pub const OIDC_STATE_COOKIE: &str = "oidc_state";
+// tells api keys apart from tokens, in logs and secret scanners
+const API_KEY_PREFIX: &str = "qa_";
+
 #[derive(Debug, Clone)]
 pub struct AuthenticationController {
     repository: Arc<Repository>,
+    revocations: Arc<RevocationStore>,
+    mailer: Arc<dyn Mailer>,
+    throttle: Arc<LoginThrottle>,
+    keyring: Arc<Keyring>,
+    // None unless an OpenID Connect provider is configured
+    oidc: Option<Arc<OidcClient>>,
+    // verified against when the email is unknown, see `login`
+    dummy_hash: PasswordHash,
+    trust_forwarded_for: bool,
+    access_token_ttl: chrono::Duration,
+    refresh_token_ttl: chrono::Duration,
+    password_reset_ttl: chrono::Duration,
+    email_verification_ttl: chrono::Duration,
+    public_url: String,
+    totp_issuer: String,
+    account_deletion: config::AccountDeletion,
+    // to take back what the votes of a deleted account earned
+    reputation_points: config::ReputationPoints,
+    hasher: Arc<PasswordHasher>,
 }
 
 impl AuthenticationController {
-    pub fn new(store: Arc<Repository>) -> Self {
-        Self { repository: store }
+    pub fn new(
+        store: Arc<Repository>,
+        revocations: Arc<RevocationStore>,
+        mailer: Arc<dyn Mailer>,
+        throttle: Arc<LoginThrottle>,
+        config: &config::Config,
+    ) -> Self {
+        Self {
+            repository: store,
+            revocations,
+            mailer,
+            throttle,
+            keyring: Arc::new(Keyring::new(config)),
+            oidc: OidcClient::from_config(config).map(Arc::new),
+            dummy_hash: hash_password(&rand::thread_rng().gen::<[u8; 32]>(), &config.argon2)
+                .expect("argon2 parameters are checked by Config"),
+            hasher: Arc::new(PasswordHasher::new(config)),
+            trust_forwarded_for: config.trust_forwarded_for,
+            access_token_ttl: chrono::Duration::minutes(config.access_token_minutes),
+            refresh_token_ttl: chrono::Duration::days(config.refresh_token_days),
+            password_reset_ttl: chrono::Duration::minutes(config.password_reset_minutes),
+            email_verification_ttl: chrono::Duration::hours(config.email_verification_hours),
+            public_url: config.public_url.clone(),
+            totp_issuer: config.totp_issuer.clone(),
+            account_deletion: config.account_deletion,
+            reputation_points: config.reputation_points,
+        }
     }
 
     // The Future type itself requires a lifetime parameter to be specified, which is used to specify the
@@ -30,93 +117,1145 @@ impl AuthenticationController {
     // lifetime parameter and let the Rust compiler infer the lifetime automatically.
     pub fn register_account(
         &self,
-        account: Account,
+        registration: RegistrationRequest,
     ) -> impl warp::Future<Output = Result<impl Reply, Rejection>> + Send + '_ {
-        let hashed_password = self.hash_password(account.password.as_bytes());
+        async move {
+            if !is_valid_email(&registration.email) {
+                return Err(custom(Error::InvalidEmail));
+            }
 
-        let account = Account {
-            id: account.id.to_owned(),
-            email: account.email.to_owned(),
-            password: hashed_password,
-        };
+            let account = NewAccount {
+                email: registration.email.to_owned(),
+                password: self.hasher.hash(registration.password.as_bytes()).await?,
+            };
+            let token = generate_token();
 
-        async move {
-            match self.repository.add_account(account).await {
-                Ok(_) => Ok(warp::reply::with_status("Account added", StatusCode::OK)),
+            match self
+                .repository
+                .add_account(
+                    account,
+                    &hash_token(&token),
+                    self.email_verification_ttl.num_seconds(),
+                )
+                .await
+            {
+                Ok(_) => {
+                    self.send_verification_mail(registration.email, &token).await;
+                    Ok(warp::reply::with_status("Account added", StatusCode::OK))
+                }
                 Err(e) => Err(warp::reject::custom(e)),
             }
         }
     }
 
+    pub fn verify_email(
+        &self,
+        verification: EmailVerification,
+    ) -> impl Future<Output = Result<impl Reply, Rejection>> + Send + '_ {
+        async move {
+            match self
+                .repository
+                .verify_email(&hash_token(&verification.token))
+                .await
+            {
+                Ok(_) => Ok(warp::reply::with_status("Email verified", StatusCode::OK)),
+                Err(_) => Err(custom(Error::InvalidVerificationToken)),
+            }
+        }
+    }
+
+    // for a lost or expired verification mail. Earlier links keep working
+    // until they expire.
+    pub fn resend_verification(
+        &self,
+        request: VerificationResendRequest,
+    ) -> impl Future<Output = Result<impl Reply, Rejection>> + Send + '_ {
+        async move {
+            // the same answer for unknown and already verified emails, like
+            // `request_password_reset`
+            if let Ok(account) = self.repository.get_account(&request.email).await {
+                if !self.repository.is_account_verified(account.id.clone()).await? {
+                    let token = generate_token();
+                    self.repository
+                        .add_email_verification_token(
+                            account.id,
+                            &hash_token(&token),
+                            self.email_verification_ttl.num_seconds(),
+                        )
+                        .await?;
+                    self.send_verification_mail(account.email, &token).await;
+                }
+            }
+
+            Ok(warp::reply::with_status(
+                "If the account exists and isn't verified yet, a verification email was sent",
+                StatusCode::OK,
+            ))
+        }
+    }
+
     pub fn login(
         &self,
-        login: Account,
+        login: LoginRequest,
+        client: ClientInfo,
+    ) -> impl Future<Output = Result<impl Reply, Rejection>> + Send + '_ {
+        async move {
+            let account_key = LoginThrottle::account_key(&login.email);
+            let mut keys = vec![account_key.clone()];
+            keys.extend(client.ip.as_ref().map(LoginThrottle::ip_key));
+
+            if let Some(retry_after) = self.throttle.retry_after(&keys) {
+                return Err(custom(Error::TooManyAttempts(retry_after)));
+            }
+
+            // an unknown email is checked against a dummy hash, so it takes as long
+            // and answers the same as a wrong password for an existing account.
+            let account = match self.repository.get_account(&login.email).await {
+                Ok(account) => Some(account),
+                Err(RepositoryError::DatabaseQueryError(sqlx::Error::RowNotFound)) => None,
+                Err(e) => return Err(custom(e)),
+            };
+            let hash = account
+                .as_ref()
+                .map_or(&self.dummy_hash, |account| &account.password);
+
+            let verified = self.hasher.verify(hash, login.password.as_bytes()).await?;
+
+            // the only time the plain password is at hand to upgrade the hash.
+            // Failing to do so is no reason to refuse the login, the next one retries.
+            if let Some(account) = account.as_ref().filter(|_| verified) {
+                if self.hasher.needs_rehash(&account.password) {
+                    self.rehash_password(account, login.password.as_bytes()).await;
+                }
+            }
+
+            match account {
+                // the password alone isn't enough, /login/2fa finishes the login
+                Some(account) if verified && account.two_factor_enabled => {
+                    self.throttle.clear(&account_key);
+                    let challenge_token = self.issue_challenge_token(account.id).await?;
+                    Ok(json(&TwoFactorChallenge {
+                        two_factor_required: true,
+                        challenge_token,
+                    })
+                    .into_response())
+                }
+                Some(account) if verified => {
+                    self.throttle.clear(&account_key);
+                    let tokens = self
+                        .issue_token_pair(
+                            account.id,
+                            account.role,
+                            &generate_token(),
+                            None,
+                            &client,
+                            LoginMethod::Password,
+                        )
+                        .await?;
+                    Ok(json(&tokens).into_response())
+                }
+                _ => {
+                    self.throttle.record_failure(&keys);
+                    Err(custom(Error::WrongCredentials))
+                }
+            }
+        }
+    }
+
+    pub fn complete_two_factor_login(
+        &self,
+        response: ChallengeResponse,
+        client: ClientInfo,
+    ) -> impl Future<Output = Result<impl Reply, Rejection>> + Send + '_ {
+        async move {
+            let challenge = self.verify_challenge_token(&response.challenge_token)?;
+            let account = self
+                .repository
+                .get_account_by_id(challenge.account_id)
+                .await?;
+
+            let account_key = LoginThrottle::account_key(&account.email);
+            let mut keys = vec![account_key.clone()];
+            keys.extend(client.ip.as_ref().map(LoginThrottle::ip_key));
+
+            if let Some(retry_after) = self.throttle.retry_after(&keys) {
+                return Err(custom(Error::TooManyAttempts(retry_after)));
+            }
+
+            // one attempt per challenge, a wrong code means starting over at /login
+            self.repository
+                .use_login_challenge(&challenge.jti)
+                .await
+                .map_err(|_| custom(Error::InvalidChallenge))?;
+
+            if !self
+                .verify_second_factor(account.id.clone(), &response.code)
+                .await?
+            {
+                self.throttle.record_failure(&keys);
+                return Err(custom(Error::InvalidTwoFactorCode));
+            }
+
+            self.throttle.clear(&account_key);
+            let tokens = self
+                .issue_token_pair(
+                    account.id,
+                    account.role,
+                    &generate_token(),
+                    None,
+                    &client,
+                    LoginMethod::Password,
+                )
+                .await?;
+            Ok(json(&tokens))
+        }
+    }
+
+    // sends the browser to the identity provider, see `complete_oidc_login`
+    pub fn start_oidc_login(&self) -> impl Future<Output = Result<impl Reply, Rejection>> + Send + '_ {
+        async move {
+            let oidc = self
+                .oidc
+                .as_ref()
+                .ok_or_else(|| custom(OidcError::NotConfigured))?;
+
+            let state = generate_token();
+            let login = OidcLogin {
+                code_verifier: generate_token(),
+                nonce: generate_token(),
+            };
+
+            let url = oidc
+                .authorization_url(&state, &login.nonce, &login.code_verifier)
+                .await?;
+            self.repository
+                .add_oidc_login(
+                    &hash_token(&state),
+                    &login,
+                    chrono::Duration::minutes(OIDC_LOGIN_TTL_MINUTES).num_seconds(),
+                )
+                .await?;
+
+            // the callback only counts when it comes back to this browser,
+            // otherwise a leaked or planted callback url logs anyone in
+            let cookie = self.oidc_state_cookie(
+                &state,
+                chrono::Duration::minutes(OIDC_LOGIN_TTL_MINUTES).num_seconds(),
+            );
+            Ok(warp::reply::with_header(
+                warp::reply::with_header(
+                    warp::reply::with_status("Redirecting to the identity provider", StatusCode::FOUND),
+                    warp::hyper::header::LOCATION,
+                    url,
+                ),
+                warp::hyper::header::SET_COOKIE,
+                cookie,
+            ))
+        }
+    }
+
+    // the identity provider redirects here. Known subjects log into their
+    // account, new ones get an account on the spot. Second factors are up
+    // to the provider, local 2FA only guards password logins.
+    pub fn complete_oidc_login(
+        &self,
+        callback: OidcCallback,
+        state_cookie: Option<String>,
+        client: ClientInfo,
+    ) -> impl Future<Output = Result<impl Reply, Rejection>> + Send + '_ {
+        async move {
+            let oidc = self
+                .oidc
+                .as_ref()
+                .ok_or_else(|| custom(OidcError::NotConfigured))?;
+
+            // checked before the state is used up, so a stranger's callback
+            // can't spend the login of the browser it belongs to
+            let same_browser = state_cookie.is_some_and(|cookie| {
+                ring::constant_time::verify_slices_are_equal(
+                    cookie.as_bytes(),
+                    callback.state.as_bytes(),
+                )
+                .is_ok()
+            });
+            if !same_browser {
+                return Err(custom(OidcError::BrowserMismatch));
+            }
+
+            let login = self
+                .repository
+                .use_oidc_login(&hash_token(&callback.state))
+                .await
+                .map_err(|_| custom(OidcError::InvalidState))?;
+
+            if let Some(error) = callback.error {
+                return Err(custom(OidcError::LoginRefused(error)));
+            }
+            let code = callback.code.ok_or_else(|| {
+                custom(OidcError::InvalidProviderResponse("callback without a code".to_string()))
+            })?;
+
+            let identity = oidc
+                .exchange_code(&code, &login.code_verifier, &login.nonce)
+                .await?;
+
+            let account = match self
+                .repository
+                .get_account_by_identity(&identity.issuer, &identity.subject)
+                .await
+            {
+                Ok(account) => account,
+                Err(RepositoryError::DatabaseQueryError(sqlx::Error::RowNotFound)) => {
+                    let email = identity
+                        .email
+                        .clone()
+                        .filter(|email| is_valid_email(email))
+                        .ok_or_else(|| custom(OidcError::InvalidIdToken("no usable email claim")))?;
+
+                    // nobody knows this password, the account logs in through
+                    // the provider until a password is set with a reset.
+                    let random = rand::thread_rng().gen::<[u8; 32]>();
+                    let password = self.hasher.hash(&random).await?;
+                    let account_id = self
+                        .repository
+                        .add_account_identity(&identity, &email, &password)
+                        .await?
+                        .ok_or_else(|| custom(OidcError::EmailInUse))?;
+                    self.repository.get_account_by_id(account_id).await?
+                }
+                Err(e) => return Err(custom(e)),
+            };
+
+            let tokens = self
+                .issue_token_pair(
+                    account.id,
+                    account.role,
+                    &generate_token(),
+                    None,
+                    &client,
+                    LoginMethod::Oidc,
+                )
+                .await?;
+            Ok(warp::reply::with_header(
+                json(&tokens),
+                warp::hyper::header::SET_COOKIE,
+                self.oidc_state_cookie("", 0),
+            ))
+        }
+    }
+
+    // starts (or restarts) enrollment. 2FA is only enforced once a code
+    // generated from the secret was confirmed.
+    pub fn enroll_two_factor(
+        &self,
+        session: Session,
+    ) -> impl Future<Output = Result<impl Reply, Rejection>> + Send + '_ {
+        async move {
+            let account = self
+                .repository
+                .get_account_by_id(session.account_id.clone())
+                .await?;
+            let secret = totp::generate_secret();
+
+            if !self
+                .repository
+                .set_totp_secret(session.account_id, &secret)
+                .await?
+            {
+                return Err(custom(Error::TwoFactorAlreadyEnabled));
+            }
+
+            Ok(json(&TwoFactorEnrollment {
+                otpauth_uri: totp::otpauth_uri(&self.totp_issuer, &account.email, &secret),
+                secret,
+            }))
+        }
+    }
+
+    // codes are guessed as easily here as at /login/2fa, failures count
+    // against the same throttle.
+    pub fn confirm_two_factor(
+        &self,
+        session: Session,
+        confirmation: TwoFactorCode,
+        client: ClientInfo,
+    ) -> impl Future<Output = Result<impl Reply, Rejection>> + Send + '_ {
+        async move {
+            let account = self
+                .repository
+                .get_account_by_id(session.account_id.clone())
+                .await?;
+
+            let account_key = LoginThrottle::account_key(&account.email);
+            let mut keys = vec![account_key.clone()];
+            keys.extend(client.ip.as_ref().map(LoginThrottle::ip_key));
+
+            if let Some(retry_after) = self.throttle.retry_after(&keys) {
+                return Err(custom(Error::TooManyAttempts(retry_after)));
+            }
+
+            let state = self
+                .repository
+                .get_totp(session.account_id.clone())
+                .await?;
+
+            let secret = match state.secret {
+                Some(secret) if !state.enabled => secret,
+                Some(_) => return Err(custom(Error::TwoFactorAlreadyEnabled)),
+                None => return Err(custom(Error::TwoFactorNotEnrolled)),
+            };
+
+            let step = match totp::verify(&secret, &confirmation.code, Utc::now().timestamp()) {
+                Some(step) => step,
+                None => {
+                    self.throttle.record_failure(&keys);
+                    return Err(custom(Error::InvalidTwoFactorCode));
+                }
+            };
+            self.throttle.clear(&account_key);
+            self.repository
+                .use_totp_step(session.account_id.clone(), step)
+                .await?;
+
+            let recovery_codes = totp::generate_recovery_codes(RECOVERY_CODES);
+            let hashes = recovery_codes
+                .iter()
+                .map(|code| hash_token(&totp::normalize_recovery_code(code)))
+                .collect();
+
+            if !self
+                .repository
+                .enable_totp(session.account_id, hashes)
+                .await?
+            {
+                return Err(custom(Error::TwoFactorNotEnrolled));
+            }
+
+            Ok(json(&RecoveryCodes { recovery_codes }))
+        }
+    }
+
+    pub fn disable_two_factor(
+        &self,
+        session: Session,
+        confirmation: TwoFactorCode,
+        client: ClientInfo,
+    ) -> impl Future<Output = Result<impl Reply, Rejection>> + Send + '_ {
+        async move {
+            let account = self
+                .repository
+                .get_account_by_id(session.account_id.clone())
+                .await?;
+
+            let account_key = LoginThrottle::account_key(&account.email);
+            let mut keys = vec![account_key.clone()];
+            keys.extend(client.ip.as_ref().map(LoginThrottle::ip_key));
+
+            if let Some(retry_after) = self.throttle.retry_after(&keys) {
+                return Err(custom(Error::TooManyAttempts(retry_after)));
+            }
+
+            if !self
+                .verify_second_factor(session.account_id.clone(), &confirmation.code)
+                .await?
+            {
+                self.throttle.record_failure(&keys);
+                return Err(custom(Error::InvalidTwoFactorCode));
+            }
+            self.throttle.clear(&account_key);
+
+            self.repository.disable_totp(session.account_id).await?;
+
+            Ok(warp::reply::with_status(
+                "Two-factor authentication disabled",
+                StatusCode::OK,
+            ))
+        }
+    }
+
+    pub fn refresh_token(
+        &self,
+        request: RefreshRequest,
+    ) -> impl Future<Output = Result<impl Reply, Rejection>> + Send + '_ {
+        async move {
+            let current = match self
+                .repository
+                .get_refresh_token(&hash_token(&request.refresh_token))
+                .await
+            {
+                Ok(token) => token,
+                Err(_) => return Err(custom(Error::InvalidRefreshToken)),
+            };
+
+            if current.revoked || current.expired {
+                return Err(custom(Error::InvalidRefreshToken));
+            }
+
+            // a refresh token is good for one exchange only. Seeing it again means
+            // it was copied, so nobody holding this family can be trusted anymore.
+            if current.used {
+                self.repository
+                    .revoke_refresh_token_family(&current.family_id)
+                    .await?;
+                return Err(custom(Error::RefreshTokenReused));
+            }
+
+            // the role may have changed since the family was started
+            let account = self
+                .repository
+                .get_account_by_id(current.account_id.clone())
+                .await?;
+
+            let refresh_token = generate_token();
+            let rotated = self
+                .repository
+                .rotate_refresh_token(
+                    &current,
+                    &hash_token(&refresh_token),
+                    self.refresh_token_ttl.num_seconds(),
+                )
+                .await?;
+
+            if !rotated {
+                self.repository
+                    .revoke_refresh_token_family(&current.family_id)
+                    .await?;
+                return Err(custom(Error::RefreshTokenReused));
+            }
+
+            Ok(json(&TokenPair {
+                access_token: self.issue_token(
+                    current.account_id,
+                    account.role,
+                    &current.family_id,
+                    current.scopes.as_deref(),
+                ),
+                refresh_token,
+            }))
+        }
+    }
+
+    // a separate login limited to some scopes, with its own refresh token.
+    // Logging out of the current session doesn't end it.
+    pub fn scoped_token(
+        &self,
+        session: Session,
+        request: ScopedTokenRequest,
+        client: ClientInfo,
+    ) -> impl Future<Output = Result<impl Reply, Rejection>> + Send + '_ {
+        async move {
+            // a key would outlive its own revocation through the tokens it minted
+            if session.api_key_id.is_some() {
+                return Err(custom(Error::Unauthorized));
+            }
+
+            if request.scopes.is_empty() {
+                return Err(custom(Error::InvalidScopes));
+            }
+
+            if !request.scopes.iter().all(|scope| session.has_scope(*scope)) {
+                return Err(custom(Error::InsufficientScope));
+            }
+
+            let account = self
+                .repository
+                .get_account_by_id(session.account_id)
+                .await?;
+
+            let tokens = self
+                .issue_token_pair(
+                    account.id,
+                    account.role,
+                    &generate_token(),
+                    Some(&request.scopes),
+                    &client,
+                    LoginMethod::Scoped,
+                )
+                .await?;
+            Ok(json(&tokens))
+        }
+    }
+
+    pub fn logout(
+        &self,
+        session: Session,
+    ) -> impl Future<Output = Result<impl Reply, Rejection>> + Send + '_ {
+        async move {
+            // there is no token to revoke, logging out with a key retires the key
+            if let Some(api_key_id) = session.api_key_id {
+                self.repository
+                    .revoke_api_key(session.account_id, api_key_id)
+                    .await?;
+                return Ok(warp::reply::with_status("Logged out", StatusCode::OK));
+            }
+
+            self.revocations.revoke(&session.jti, session.exp).await?;
+            self.repository
+                .revoke_refresh_token_family(&session.sid)
+                .await?;
+
+            Ok(warp::reply::with_status("Logged out", StatusCode::OK))
+        }
+    }
+
+    pub fn change_password(
+        &self,
+        session: Session,
+        change: PasswordChange,
+    ) -> impl Future<Output = Result<impl Reply, Rejection>> + Send + '_ {
+        async move {
+            let account = self
+                .repository
+                .get_account_by_id(session.account_id.clone())
+                .await?;
+
+            if !self.hasher.verify(&account.password, change.old_password.as_bytes()).await? {
+                return Err(custom(Error::WrongCredentials));
+            }
+
+            let hashed_password = self.hasher.hash(change.new_password.as_bytes()).await?;
+            self.repository
+                .update_account_password(session.account_id.clone(), &hashed_password)
+                .await?;
+
+            // every other device has to log in again with the new password
+            self.repository
+                .revoke_account_refresh_tokens(session.account_id, Some(&session.sid))
+                .await?;
+
+            Ok(warp::reply::with_status("Password changed", StatusCode::OK))
+        }
+    }
+
+    // what happens to the account's questions and answers depends on the
+    // configuration. Its sessions and api keys are deleted with it.
+    pub fn delete_account(
+        &self,
+        session: Session,
+        deletion: AccountDeletionRequest,
+    ) -> impl Future<Output = Result<impl Reply, Rejection>> + Send + '_ {
+        async move {
+            let account = self
+                .repository
+                .get_account_by_id(session.account_id.clone())
+                .await?;
+
+            // accounts created by an oidc login have a password nobody knows,
+            // having just logged in through the provider proves it's them instead
+            if account.has_password {
+                let password = deletion.password.unwrap_or_default();
+                if !self.hasher.verify(&account.password, password.as_bytes()).await? {
+                    return Err(custom(Error::WrongCredentials));
+                }
+            } else if session.api_key_id.is_some()
+                || !self
+                    .repository
+                    .is_recent_login(
+                        &session.sid,
+                        LoginMethod::Oidc,
+                        chrono::Duration::minutes(RECENT_LOGIN_MINUTES).num_seconds(),
+                    )
+                    .await?
+            {
+                return Err(custom(Error::RecentLoginRequired));
+            }
+
+            self.repository
+                .delete_account(session.account_id, self.account_deletion, self.reputation_points)
+                .await?;
+
+            Ok(warp::reply::with_status("Account deleted", StatusCode::OK))
+        }
+    }
+
+    pub fn request_password_reset(
+        &self,
+        request: PasswordResetRequest,
+    ) -> impl Future<Output = Result<impl Reply, Rejection>> + Send + '_ {
+        async move {
+            // the answer is the same whether the account exists or not,
+            // so this can't be used to find out which emails are registered.
+            if let Ok(account) = self.repository.get_account(&request.email).await {
+                let token = generate_token();
+
+                self.repository
+                    .add_password_reset_token(
+                        account.id,
+                        &hash_token(&token),
+                        self.password_reset_ttl.num_seconds(),
+                    )
+                    .await?;
+
+                let mail = Mail {
+                    to: account.email,
+                    subject: "Password reset".to_string(),
+                    body: format!(
+                        "Use this token to choose a new password through POST /password-reset/confirm.\n\
+                         It expires in {} minutes and can only be used once.\n\n{}",
+                        self.password_reset_ttl.num_minutes(),
+                        token
+                    ),
+                };
+
+                if let Err(e) = self.mailer.send(mail).await {
+                    eprintln!("cannot send password reset mail: {}", e);
+                }
+            }
+
+            Ok(warp::reply::with_status(
+                "If the account exists, a password reset email was sent",
+                StatusCode::OK,
+            ))
+        }
+    }
+
+    pub fn confirm_password_reset(
+        &self,
+        confirmation: PasswordResetConfirmation,
+    ) -> impl Future<Output = Result<impl Reply, Rejection>> + Send + '_ {
+        async move {
+            let account_id = match self
+                .repository
+                .use_password_reset_token(&hash_token(&confirmation.token))
+                .await
+            {
+                Ok(account_id) => account_id,
+                Err(_) => return Err(custom(Error::InvalidResetToken)),
+            };
+
+            let hashed_password = self.hasher.hash(confirmation.new_password.as_bytes()).await?;
+            self.repository
+                .update_account_password(account_id.clone(), &hashed_password)
+                .await?;
+            self.repository
+                .revoke_account_refresh_tokens(account_id, None)
+                .await?;
+
+            Ok(warp::reply::with_status("Password changed", StatusCode::OK))
+        }
+    }
+
+    pub fn create_api_key(
+        &self,
+        session: Session,
+        request: ApiKeyRequest,
+    ) -> impl Future<Output = Result<impl Reply, Rejection>> + Send + '_ {
+        async move {
+            // same as `scoped_token`, keys minted by a key outlive its revocation
+            if session.api_key_id.is_some() {
+                return Err(custom(Error::Unauthorized));
+            }
+
+            let name = request.name.trim();
+            if name.is_empty()
+                || name.chars().count() > API_KEY_NAME_MAX_LENGTH
+                || request
+                    .expires_in_days
+                    .is_some_and(|days| !(1..=API_KEY_MAX_EXPIRY_DAYS).contains(&days))
+            {
+                return Err(custom(Error::InvalidApiKey));
+            }
+
+            // a key never gets more than the session creating it
+            let scopes = match request.scopes {
+                Some(scopes) if scopes.iter().all(|scope| session.has_scope(*scope)) => scopes,
+                Some(_) => return Err(custom(Error::InsufficientScope)),
+                None => session.scopes.clone().unwrap_or_else(|| Scope::ALL.to_vec()),
+            };
+
+            let key = format!("{}{}", API_KEY_PREFIX, generate_token());
+            let api_key = self
+                .repository
+                .add_api_key(
+                    session.account_id,
+                    name,
+                    &key[..API_KEY_PREFIX.len() + 8],
+                    &hash_token(&key),
+                    scopes.iter().map(Scope::to_string).collect(),
+                    request
+                        .expires_in_days
+                        .map(|days| chrono::Duration::days(days).num_seconds()),
+                )
+                .await?;
+
+            Ok(warp::reply::with_status(
+                json(&CreatedApiKey { key, api_key }),
+                StatusCode::CREATED,
+            ))
+        }
+    }
+
+    pub fn get_api_keys(
+        &self,
+        session: Session,
+    ) -> impl Future<Output = Result<impl Reply, Rejection>> + Send + '_ {
+        async move {
+            let api_keys = self.repository.get_api_keys(session.account_id).await?;
+            Ok(json(&api_keys))
+        }
+    }
+
+    pub fn revoke_api_key(
+        &self,
+        session: Session,
+        api_key_id: i32,
+    ) -> impl Future<Output = Result<impl Reply, Rejection>> + Send + '_ {
+        async move {
+            self.repository
+                .revoke_api_key(session.account_id, ApiKeyId(api_key_id))
+                .await?;
+            Ok(warp::reply::with_status("API key revoked", StatusCode::OK))
+        }
+    }
+
+    pub fn get_sessions(
+        &self,
+        session: Session,
+    ) -> impl Future<Output = Result<impl Reply, Rejection>> + Send + '_ {
+        async move {
+            let sessions = self
+                .repository
+                .get_sessions(session.account_id, &session.sid)
+                .await?;
+            Ok(json(&sessions))
+        }
+    }
+
+    // also ends the refresh token family, the device can't log back in silently
+    pub fn delete_session(
+        &self,
+        session: Session,
+        session_id: i32,
     ) -> impl Future<Output = Result<impl Reply, Rejection>> + Send + '_ {
         async move {
-            match self.repository.get_account(&login.email).await {
-                Ok(account) => {
-                    match self.verify_password(&account.password, login.password.as_bytes()) {
-                        Ok(verified) => {
-                            if verified {
-                                Ok(json(
-                                    &self.issue_token(account.id.expect("Account Id not found")),
-                                ))
-                            } else {
-                                Err(custom(Error::WrongCredentials))
+            self.repository
+                .delete_session(session.account_id, session_id)
+                .await?;
+            Ok(warp::reply::with_status("Session signed out", StatusCode::OK))
+        }
+    }
+
+    pub fn paseto_keys(&self) -> impl Future<Output = Result<impl Reply, Rejection>> + Send + '_ {
+        async move { Ok(json(&serde_json::json!({ "keys": self.keyring.public_keys() }))) }
+    }
+
+    // the caller's address and user agent. X-Forwarded-For is only
+    // believed when configured, clients can forge it.
+    pub fn client_info(&self) -> impl Filter<Extract = (ClientInfo,), Error = Rejection> + Clone {
+        let trust_forwarded_for = self.trust_forwarded_for;
+        addr::remote()
+            .and(header::optional::<String>("x-forwarded-for"))
+            .and(header::optional::<String>("user-agent"))
+            .map(
+                move |remote: Option<SocketAddr>,
+                      forwarded_for: Option<String>,
+                      user_agent: Option<String>| {
+                    let forwarded = forwarded_for
+                        .filter(|_| trust_forwarded_for)
+                        .and_then(|value| value.split(',').next()?.trim().parse::<IpAddr>().ok());
+                    ClientInfo {
+                        ip: forwarded.or_else(|| remote.map(|remote| remote.ip())),
+                        user_agent,
+                    }
+                },
+            )
+    }
+
+    // a session comes either from a token in `Authorization`, or from an api
+    // key sent as `Authorization: ApiKey <key>` or in `X-Api-Key`.
+    pub fn auth(&self) -> impl Filter<Extract = (Session,), Error = Rejection> + Clone {
+        let keyring = Arc::clone(&self.keyring);
+        let revocations = Arc::clone(&self.revocations);
+        let repository = Arc::clone(&self.repository);
+        let access_token_ttl = self.access_token_ttl;
+        header::optional::<String>("Authorization")
+            .and(header::optional::<String>("x-api-key"))
+            .and_then(move |authorization: Option<String>, api_key: Option<String>| {
+                let keyring = Arc::clone(&keyring);
+                let revocations = Arc::clone(&revocations);
+                let repository = Arc::clone(&repository);
+                async move {
+                    let api_key = api_key.or_else(|| {
+                        authorization
+                            .as_deref()
+                            .and_then(|value| value.strip_prefix("ApiKey "))
+                            .map(|key| key.trim().to_string())
+                    });
+
+                    let session = match (api_key, authorization) {
+                        (Some(key), _) => {
+                            Self::verify_api_key(&repository, &key, access_token_ttl).await
+                        }
+                        (None, Some(token)) => {
+                            match Self::verify_token(&keyring, &revocations, token) {
+                                Ok(session) => Self::check_session(&repository, session).await,
+                                Err(e) => Err(e),
                             }
                         }
-                        Err(e) => Err(custom(Error::ArgonLibraryError(e))),
-                    }
+                        (None, None) => Err(Error::Unauthenticated),
+                    };
+
+                    session.map_err(|e| match e {
+                        Error::SessionLookupFailed(_) => warp::reject::custom(e),
+                        _ => warp::reject::custom(Error::Unauthenticated),
+                    })
                 }
-                Err(e) => Err(custom(e)),
+            })
+    }
+
+    // authorization on top of `auth`: the session must hold at least `role`.
+    // Role-guarded routes are administration, which scoped credentials only
+    // reach with the admin scope.
+    pub fn require_role(
+        &self,
+        role: Role,
+    ) -> impl Filter<Extract = (Session,), Error = Rejection> + Clone {
+        self.auth().and_then(move |session: Session| {
+            if !session.has_scope(Scope::Admin) {
+                future::ready(Err(warp::reject::custom(Error::InsufficientScope)))
+            } else if session.role >= role {
+                future::ready(Ok(session))
+            } else {
+                future::ready(Err(warp::reject::custom(Error::Unauthorized)))
             }
-        }
+        })
     }
 
-    pub fn auth() -> impl Filter<Extract = (Session,), Error = Rejection> + Clone {
-        header::<String>("Authorization").and_then(|token: String| {
-            match Self::verify_token(token) {
-                Ok(t) => return future::ready(Ok(t)),
-                Err(_) => return future::ready(Err(warp::reject::custom(Error::Unauthorized))),
+    // `auth` for routes an api key or scoped token needs `scope` to reach.
+    pub fn require_scope(
+        &self,
+        scope: Scope,
+    ) -> impl Filter<Extract = (Session,), Error = Rejection> + Clone {
+        self.auth().and_then(move |session: Session| {
+            if session.has_scope(scope) {
+                future::ready(Ok(session))
+            } else {
+                future::ready(Err(warp::reject::custom(Error::InsufficientScope)))
             }
         })
     }
 
-    fn hash_password(&self, password: &[u8]) -> String {
-        let salt = rand::thread_rng().gen::<[u8; 32]>();
-        let config = Config::default();
-        argon2::hash_encoded(password, &salt, &config).unwrap()
+    async fn rehash_password(&self, account: &Account, password: &[u8]) {
+        let rehashed = match self.hasher.hash(password).await {
+            Ok(rehashed) => rehashed,
+            Err(_) => return,
+        };
+
+        if let Err(e) = self
+            .repository
+            .update_password_hash(account.id.clone(), &account.password, &rehashed)
+            .await
+        {
+            eprintln!("cannot rehash password: {}", e);
+        }
+    }
+
+    // `code` is a totp code or, failing that, one of the recovery codes.
+    // Either is only accepted once.
+    async fn verify_second_factor(&self, account_id: AccountId, code: &str) -> Result<bool, Rejection> {
+        let state = self.repository.get_totp(account_id.clone()).await?;
+
+        let secret = match state.secret {
+            Some(secret) if state.enabled => secret,
+            _ => return Err(custom(Error::TwoFactorNotEnrolled)),
+        };
+
+        if let Some(step) = totp::verify(&secret, code, Utc::now().timestamp()) {
+            return Ok(self.repository.use_totp_step(account_id, step).await?);
+        }
+
+        Ok(self
+            .repository
+            .use_recovery_code(account_id, &hash_token(&totp::normalize_recovery_code(code)))
+            .await?)
+    }
+
+    fn oidc_state_cookie(&self, state: &str, max_age_seconds: i64) -> String {
+        let secure = if self.public_url.starts_with("https://") { "; Secure" } else { "" };
+        format!(
+            "{}={}; Path=/login/oidc; Max-Age={}; HttpOnly; SameSite=Lax{}",
+            OIDC_STATE_COOKIE, state, max_age_seconds, secure
+        )
+    }
+
+    async fn send_verification_mail(&self, email: String, token: &str) {
+        let mail = Mail {
+            to: email,
+            subject: "Confirm your email address".to_string(),
+            body: format!(
+                "Open this link to confirm your email address, it expires in {} hours:\n\n\
+                 {}/registration/verify?token={}",
+                self.email_verification_ttl.num_hours(),
+                self.public_url,
+                token
+            ),
+        };
+
+        // the account exists at this point, a failed delivery shouldn't fail the registration.
+        // The mail can be sent again through /registration/resend.
+        if let Err(e) = self.mailer.send(mail).await {
+            eprintln!("cannot send verification mail: {}", e);
+        }
     }
 
-    fn verify_password(&self, hash: &str, password: &[u8]) -> Result<bool, argon2::Error> {
-        argon2::verify_encoded(hash, password)
+    // starts a new refresh token family and its session record,
+    // one per successful login or scoped token request.
+    async fn issue_token_pair(
+        &self,
+        account_id: AccountId,
+        role: Role,
+        family_id: &str,
+        scopes: Option<&[Scope]>,
+        client: &ClientInfo,
+        method: LoginMethod,
+    ) -> Result<TokenPair, Rejection> {
+        let refresh_token = generate_token();
+
+        self.repository
+            .add_session(account_id.clone(), family_id, client, method)
+            .await?;
+
+        self.repository
+            .add_refresh_token(
+                account_id.clone(),
+                family_id,
+                &hash_token(&refresh_token),
+                self.refresh_token_ttl.num_seconds(),
+                scopes.map(|scopes| scopes.iter().map(Scope::to_string).collect()),
+            )
+            .await?;
+
+        Ok(TokenPair {
+            access_token: self.issue_token(account_id, role, family_id, scopes),
+            refresh_token,
+        })
     }
 
-    fn issue_token(&self, account_id: AccountId) -> String {
-        let key = env::var("PASETO_KEY").unwrap();
+    // a null `scopes` claim means the token can do anything the role allows
+    fn issue_token(
+        &self,
+        account_id: AccountId,
+        role: Role,
+        family_id: &str,
+        scopes: Option<&[Scope]>,
+    ) -> String {
         let current_date_time = Utc::now();
-        let dt = current_date_time + chrono::Duration::days(1);
+        let dt = current_date_time + self.access_token_ttl;
 
-        PasetoBuilder::new()
-            .set_encryption_key(&Vec::from(key.as_bytes()))
+        // signed in public mode, so other services can verify sessions
+        let mut builder = PasetoBuilder::new();
+        let builder = match self.keyring.signing_key() {
+            Some((key_pair, footer)) => builder.set_ed25519_key(key_pair).set_footer(footer),
+            None => builder
+                .set_encryption_key(self.keyring.encryption_key())
+                .set_footer(self.keyring.footer()),
+        };
+
+        builder
             .set_expiration(&dt)
             .set_not_before(&Utc::now())
+            .set_jti(&generate_token())
             .set_claim("account_id", serde_json::json!(account_id))
+            .set_claim("sid", serde_json::json!(family_id))
+            .set_claim("role", serde_json::json!(role))
+            .set_claim("scopes", serde_json::json!(scopes))
             .build()
             .expect("Failed to construct paseto token w/ builder!")
     }
 
-    fn verify_token(token: String) -> Result<Session, Error> {
-        let key = env::var("PASETO_KEY").expect("please define the PASETO_KEY env variable");
-        let token = paseto::tokens::validate_local_token(
-            &token,
-            None,
-            key.as_bytes(),
-            &paseto::tokens::TimeBackend::Chrono,
-        )
-        .map_err(|_| crate::custom_errors::account::Error::CannotDecryptToken)?;
+    // the jti is kept until /login/2fa uses it up, see `use_login_challenge`
+    async fn issue_challenge_token(&self, account_id: AccountId) -> Result<String, Rejection> {
+        let jti = generate_token();
+        self.repository
+            .add_login_challenge(&jti, account_id.clone(), CHALLENGE_TTL_MINUTES * 60)
+            .await?;
+        let dt = Utc::now() + chrono::Duration::minutes(CHALLENGE_TTL_MINUTES);
+
+        Ok(PasetoBuilder::new()
+            .set_encryption_key(self.keyring.encryption_key())
+            .set_footer(self.keyring.footer())
+            .set_expiration(&dt)
+            .set_not_before(&Utc::now())
+            .set_jti(&jti)
+            .set_claim("account_id", serde_json::json!(account_id))
+            .set_claim("purpose", serde_json::json!(CHALLENGE_PURPOSE))
+            .build()
+            .expect("Failed to construct paseto token w/ builder!"))
+    }
+
+    fn verify_challenge_token(&self, token: &str) -> Result<LoginChallenge, Error> {
+        let token = self.keyring.validate(token).ok_or(Error::InvalidChallenge)?;
 
-        serde_json::from_value::<Session>(token)
-            .map_err(|_| crate::custom_errors::account::Error::CannotDecryptToken)
+        match serde_json::from_value::<LoginChallenge>(token) {
+            Ok(challenge) if challenge.purpose == CHALLENGE_PURPOSE => Ok(challenge),
+            _ => Err(Error::InvalidChallenge),
+        }
+    }
+
+    fn verify_token(
+        keyring: &Keyring,
+        revocations: &RevocationStore,
+        token: String,
+    ) -> Result<Session, Error> {
+        let token = keyring
+            .validate(&token)
+            .ok_or(crate::custom_errors::account::Error::CannotDecryptToken)?;
+
+        // challenge tokens are signed with the same key but aren't sessions
+        if token.get("purpose").is_some() {
+            return Err(Error::Unauthenticated);
+        }
+
+        let session = serde_json::from_value::<Session>(token)
+            .map_err(|_| crate::custom_errors::account::Error::CannotDecryptToken)?;
+
+        if revocations.is_revoked(&session.jti) {
+            return Err(Error::Unauthenticated);
+        }
+
+        Ok(session)
+    }
+
+    // a token is only good while the session it was issued for exists, so
+    // deleting the session signs the device out at once.
+    async fn check_session(repository: &Repository, session: Session) -> Result<Session, Error> {
+        match repository.touch_session(&session.sid).await {
+            Ok(true) => Ok(session),
+            Ok(false) => Err(Error::Unauthenticated),
+            Err(e) => Err(Error::SessionLookupFailed(e)),
+        }
+    }
+
+    // keys are looked up on every request, so a revoked key stops working at once.
+    async fn verify_api_key(
+        repository: &Repository,
+        key: &str,
+        access_token_ttl: chrono::Duration,
+    ) -> Result<Session, Error> {
+        let owner = repository
+            .use_api_key(&hash_token(key))
+            .await
+            .map_err(|e| match e {
+                RepositoryError::DatabaseQueryError(sqlx::Error::RowNotFound) => Error::Unauthenticated,
+                e => Error::SessionLookupFailed(e),
+            })?;
+
+        let now = Utc::now();
+        let exp = match owner.expires_on {
+            Some(expires_on) => expires_on.min(now + access_token_ttl),
+            None => now + access_token_ttl,
+        };
+        let id = format!("api-key:{}", owner.id.0);
+
+        Ok(Session {
+            exp,
+            account_id: owner.account_id,
+            nbf: now,
+            jti: id.clone(),
+            sid: id,
+            role: owner.role,
+            scopes: Some(owner.scopes),
+            api_key_id: Some(owner.id),
+        })
     }
 }
@@ -0,0 +1,117 @@
+use std::sync::Arc;
+
+use warp::{
+    hyper::StatusCode,
+    reject::custom,
+    reply::{json, with_status},
+    Future, Rejection, Reply,
+};
+
+use crate::{
+    config::Config,
+    custom_errors::{account, common},
+    models::{
+        account::Session,
+        comment::{CommentDTO, CommentId},
+        post::PostId,
+    },
+    repository::{database_repository::DatabaseRepository, Repository},
+};
+
+pub struct CommentController {
+    pub repository: Arc<Repository>,
+    require_verified_email: bool,
+}
+
+impl CommentController {
+    pub fn new(store: Arc<Repository>, config: &Config) -> Self {
+        Self {
+            repository: store,
+            require_verified_email: config.require_verified_email,
+        }
+    }
+
+    pub fn create_comment(
+        &self,
+        session: Session,
+        post: PostId,
+        comment: CommentDTO,
+    ) -> impl Future<Output = Result<impl Reply, Rejection>> + Send + '_ {
+        async move {
+            if !comment.is_valid() {
+                return Err(custom(common::Error::InvalidComment));
+            }
+
+            if self.require_verified_email
+                && !self
+                    .repository
+                    .is_account_verified(session.account_id.clone())
+                    .await?
+            {
+                return Err(custom(account::Error::EmailNotVerified));
+            }
+
+            let result = self
+                .repository
+                .create_comment(comment, session.account_id, post)
+                .await;
+            match result {
+                Ok(comment) => Ok(json(&comment)),
+                Err(e) => Err(custom(e)),
+            }
+        }
+    }
+
+    // moderators can edit any comment, everybody else only their own
+    pub fn update_comment(
+        &self,
+        session: Session,
+        comment_id: CommentId,
+        comment: CommentDTO,
+    ) -> impl Future<Output = Result<impl Reply, Rejection>> + Send + '_ {
+        async move {
+            if !comment.is_valid() {
+                return Err(custom(common::Error::InvalidComment));
+            }
+
+            if session.role.can_moderate()
+                || self
+                    .repository
+                    .is_comment_owner(comment_id, session.account_id)
+                    .await?
+            {
+                match self.repository.update_comment(comment, comment_id).await {
+                    Ok(comment) => Ok(json(&comment)),
+                    Err(e) => Err(custom(e)),
+                }
+            } else {
+                Err(custom(account::Error::Unauthorized))
+            }
+        }
+    }
+
+    pub fn delete_comment(
+        &self,
+        session: Session,
+        comment_id: CommentId,
+    ) -> impl Future<Output = Result<impl Reply, Rejection>> + Send + '_ {
+        async move {
+            if session.role.can_moderate()
+                || self
+                    .repository
+                    .is_comment_owner(comment_id, session.account_id)
+                    .await?
+            {
+                match self.repository.delete_comment(comment_id).await {
+                    Ok(_) => Ok(with_status(
+                        format!("comment id: {} deleted", comment_id.0),
+                        StatusCode::OK,
+                    )),
+                    Err(e) => Err(custom(e)),
+                }
+            } else {
+                Err(custom(account::Error::Unauthorized))
+            }
+        }
+    }
+}
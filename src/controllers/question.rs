@@ -8,21 +8,33 @@ use warp::{
 };
 
 use crate::{
-    custom_errors::account,
+    config::{Config, ReputationPoints},
+    custom_errors::{account, common},
     models::{
         account::Session,
         pagination::{extract_pagination, Pagination},
-        question::{QuestionDTO, QuestionId},
+        post::PostId,
+        answer::AnswerId,
+        question::{Include, QuestionDTO, QuestionDetail, QuestionId},
+        vote::{Score, Vote},
     }, repository::{Repository, database_repository::DatabaseRepository},
 };
 
 pub struct QuestionController {
     pub repository: Arc<Repository>,
+    require_verified_email: bool,
+    reputation_points: ReputationPoints,
+    reputation_to_edit: i32,
 }
 
 impl QuestionController {
-    pub fn new(store: Arc<Repository>) -> Self {
-        Self { repository: store }
+    pub fn new(store: Arc<Repository>, config: &Config) -> Self {
+        Self {
+            repository: store,
+            require_verified_email: config.require_verified_email,
+            reputation_points: config.reputation_points,
+            reputation_to_edit: config.reputation_to_edit,
+        }
     }
 
     pub fn create_question(
@@ -31,9 +43,18 @@ impl QuestionController {
         new_question: QuestionDTO,
     ) -> impl Future<Output = Result<impl Reply, Rejection>> + Send + '_ {
         async move {
+            if self.require_verified_email
+                && !self
+                    .repository
+                    .is_account_verified(session.account_id.clone())
+                    .await?
+            {
+                return Err(custom(account::Error::EmailNotVerified));
+            }
+
             match self
                 .repository
-                .create_question(new_question, session.account_id)
+                .create_question(new_question, session.account_id, self.reputation_points)
                 .await
             {
                 Ok(question) => Ok(json(&question)),
@@ -45,11 +66,46 @@ impl QuestionController {
     pub fn get_question(
         &self,
         id: i32,
+        params: HashMap<String, String>,
     ) -> impl Future<Output = Result<impl Reply, Rejection>> + Send + '_ {
         async move {
-            let result = self.repository.get_question(QuestionId(id)).await;
+            let includes = match params.get("include") {
+                Some(include) => {
+                    Some(Include::parse_list(include).ok_or(common::Error::InvalidInclude)?)
+                }
+                None => None,
+            };
+            let include = |relation| includes.as_ref().is_none_or(|list| list.contains(&relation));
+
+            let result = if includes.as_ref().is_some_and(|list| list.contains(&Include::Answers)) {
+                self.repository.get_question_with_answers(QuestionId(id)).await
+            } else {
+                self.repository
+                    .get_question(QuestionId(id))
+                    .await
+                    .map(|question| QuestionDetail {
+                        question,
+                        answers: None,
+                        comments: None,
+                    })
+            };
+
             match result {
-                Ok(question) => Ok(json(&question)),
+                Ok(mut detail) => {
+                    if !include(Include::Author) {
+                        detail.question.author = None;
+                        for answer in detail.answers.iter_mut().flatten() {
+                            answer.author = None;
+                            for comment in answer.comments.iter_mut().flatten() {
+                                comment.author = None;
+                            }
+                        }
+                        for comment in detail.comments.iter_mut().flatten() {
+                            comment.author = None;
+                        }
+                    }
+                    Ok(json(&detail))
+                }
                 Err(e) => Err(custom(e)),
             }
         }
@@ -84,18 +140,25 @@ impl QuestionController {
         question_id: QuestionId,
     ) -> impl Future<Output = Result<impl Reply, Rejection>> + Send + '_ {
         async move {
-            if self
-                .repository
-                .is_question_owner(question_id, session.account_id)
-                .await?
+            // a missing question is a 404 for everyone, whatever they may edit
+            let existing = self.repository.get_question(question_id).await?;
+            let is_owner = existing
+                .author
+                .is_some_and(|author| author.id.0 == session.account_id.0);
+
+            // moderators can edit any question, everybody else their own, and
+            // other people's once they have earned enough reputation
+            if !session.role.can_moderate()
+                && !is_owner
+                && self.repository.get_reputation(session.account_id).await? < self.reputation_to_edit
             {
-                let result = self.repository.update_question(question, question_id).await;
-                match result {
-                    Ok(question) => Ok(json(&question)),
-                    Err(e) => Err(custom(e)),
-                }
-            } else {
-                Err(custom(account::Error::Unauthorized))
+                return Err(custom(account::Error::InsufficientReputation(self.reputation_to_edit)));
+            }
+
+            let result = self.repository.update_question(question, question_id).await;
+            match result {
+                Ok(question) => Ok(json(&question)),
+                Err(e) => Err(custom(e)),
             }
         }
     }
@@ -106,7 +169,9 @@ impl QuestionController {
         question_id: QuestionId,
     ) -> impl Future<Output = Result<impl Reply, Rejection>> + Send + '_ {
         async move {
-            if self.repository.is_question_owner(question_id, session.account_id).await? {
+            if session.role.can_moderate()
+                || self.repository.is_question_owner(question_id, session.account_id).await?
+            {
                 let result = self.repository.delete_question(question_id).await;
                 match result {
                     Ok(_) => Ok(with_status(
@@ -121,4 +186,57 @@ impl QuestionController {
         }
     }
 
+    // only the question's author knows what solved the problem, so unlike
+    // edits this isn't open to moderators.
+    pub fn accept_answer(
+        &self,
+        session: Session,
+        question_id: QuestionId,
+        answer_id: Option<AnswerId>,
+    ) -> impl Future<Output = Result<impl Reply, Rejection>> + Send + '_ {
+        async move {
+            if !self.repository.is_question_owner(question_id, session.account_id).await? {
+                return Err(custom(account::Error::Unauthorized));
+            }
+
+            let result = match answer_id {
+                Some(answer_id) => self.repository.accept_answer(question_id, answer_id, self.reputation_points).await,
+                None => self.repository.unaccept_answer(question_id).await,
+            };
+            match result {
+                Ok(_) => Ok(with_status(
+                    format!("accepted answer of question id: {} updated", question_id.0),
+                    StatusCode::OK
+                )),
+                Err(e) => Err(custom(e))
+            }
+        }
+    }
+
+    // None takes the vote back
+    pub fn vote_question(
+        &self,
+        session: Session,
+        question_id: QuestionId,
+        vote: Option<Vote>,
+    ) -> impl Future<Output = Result<impl Reply, Rejection>> + Send + '_ {
+        async move {
+            if self
+                .repository
+                .is_question_owner(question_id, session.account_id.clone())
+                .await?
+            {
+                return Err(custom(common::Error::OwnContentVote));
+            }
+
+            let result = self
+                .repository
+                .cast_vote(session.account_id, PostId::Question(question_id), vote, self.reputation_points)
+                .await;
+            match result {
+                Ok(score) => Ok(json(&Score { score })),
+                Err(e) => Err(custom(e)),
+            }
+        }
+    }
 }
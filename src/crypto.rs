@@ -0,0 +1,12 @@
+use rand::Rng;
+use sha2::{Digest, Sha256};
+
+// opaque tokens (refresh tokens and the like) are handed to the client once and
+// only their digest is kept in the database, the same way we never store passwords.
+pub fn generate_token() -> String {
+    hex::encode(rand::thread_rng().gen::<[u8; 32]>())
+}
+
+pub fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
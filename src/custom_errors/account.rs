@@ -1,20 +1,47 @@
 use std::fmt::Display;
 use warp::reject::Reject;
 use argon2::Error as ArgonError;
+use super::repository::Error as RepositoryError;
 
 #[derive(Debug)]
 pub enum Error {
     WrongCredentials,
+    #[allow(clippy::enum_variant_names)]
     ArgonLibraryError(ArgonError),
     CannotDecryptToken,
+    Unauthenticated,
     Unauthorized,
+    InvalidRefreshToken,
+    RefreshTokenReused,
+    InvalidResetToken,
+    InvalidEmail,
+    InvalidVerificationToken,
+    EmailNotVerified,
+    InvalidProfile,
+    // seconds until the next attempt is allowed
+    TooManyAttempts(u64),
+    TwoFactorAlreadyEnabled,
+    TwoFactorNotEnrolled,
+    InvalidTwoFactorCode,
+    InvalidChallenge,
+    // an account without a password proves itself with a fresh oidc login
+    RecentLoginRequired,
+    InvalidApiKey,
+    InsufficientScope,
+    InvalidScopes,
+    // every password hashing slot is taken
+    HashingBusy,
+    // the reputation the action needs
+    InsufficientReputation(i32),
+    // the credentials couldn't be looked up, not that they are wrong
+    SessionLookupFailed(RepositoryError),
 }
 
 impl Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match &*self {
-            Error::ArgonLibraryError(_) => {
-                write!(f, "Can't verify password")
+        match self {
+            Error::ArgonLibraryError(err) => {
+                write!(f, "Can't hash or verify password: {}", err)
             },
             Error::WrongCredentials => {
                 write!(f, "Wrong Credentials")
@@ -22,8 +49,68 @@ impl Display for Error {
             Error::CannotDecryptToken => {
                 write!(f, "Not possible to decrypt informed token")
             },
+            Error::Unauthenticated => {
+                write!(f, "missing, invalid or revoked token")
+            },
             Error::Unauthorized => {
                 write!(f, "you are not authorized to use this resource")
+            },
+            Error::InvalidRefreshToken => {
+                write!(f, "refresh token is invalid or expired")
+            },
+            Error::RefreshTokenReused => {
+                write!(f, "refresh token was already used")
+            },
+            Error::InvalidResetToken => {
+                write!(f, "password reset token is invalid, used or expired")
+            },
+            Error::InvalidEmail => {
+                write!(f, "email address is not valid")
+            },
+            Error::InvalidVerificationToken => {
+                write!(f, "verification token is invalid, used or expired")
+            },
+            Error::EmailNotVerified => {
+                write!(f, "email address must be verified first")
+            },
+            Error::InvalidProfile => {
+                write!(f, "profile fields are too long or the avatar url is not http(s)")
+            },
+            Error::TooManyAttempts(retry_after) => {
+                write!(f, "too many failed attempts, retry in {} seconds", retry_after)
+            },
+            Error::TwoFactorAlreadyEnabled => {
+                write!(f, "two-factor authentication is already enabled")
+            },
+            Error::RecentLoginRequired => {
+                write!(f, "a recent login through the identity provider is required")
+            },
+            Error::TwoFactorNotEnrolled => {
+                write!(f, "two-factor authentication was not started or is not enabled")
+            },
+            Error::InvalidTwoFactorCode => {
+                write!(f, "two-factor code is wrong or was already used")
+            },
+            Error::InvalidChallenge => {
+                write!(f, "login challenge is invalid or expired")
+            },
+            Error::InvalidApiKey => {
+                write!(f, "api key name is empty or too long, or the expiry is not between 1 and 3650 days")
+            },
+            Error::InsufficientScope => {
+                write!(f, "the credential used lacks the scope for this resource")
+            },
+            Error::InvalidScopes => {
+                write!(f, "at least one scope must be requested")
+            },
+            Error::HashingBusy => {
+                write!(f, "too many passwords are being hashed")
+            },
+            Error::InsufficientReputation(needed) => {
+                write!(f, "{} reputation is needed for this", needed)
+            }
+            Error::SessionLookupFailed(err) => {
+                write!(f, "cannot check the session: {}", err)
             }
         }
     }
@@ -5,16 +5,24 @@ use warp::reject::Reject;
 #[derive(Debug)]
 pub enum Error {
     MissingParameters,
-    ParseError(std::num::ParseIntError),
+    Parse(std::num::ParseIntError),
+    InvalidSort,
+    InvalidInclude,
+    OwnContentVote,
+    InvalidComment,
 }
 
 impl Reject for Error {}
 
 impl Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match &*self {
+        match self {
             Error::MissingParameters => write!(f, "missing parameter on query string"),
-            Error::ParseError(ref err) => write!(f, "Cannot parse parameter: {}", err),
+            Error::Parse(ref err) => write!(f, "Cannot parse parameter: {}", err),
+            Error::InvalidSort => write!(f, "sort must be one of newest, oldest or score"),
+            Error::InvalidInclude => write!(f, "include can only list answers and author"),
+            Error::OwnContentVote => write!(f, "you can't vote on your own question or answer"),
+            Error::InvalidComment => write!(f, "comment is empty or too long"),
         }
     }
 }
\ No newline at end of file
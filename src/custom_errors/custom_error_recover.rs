@@ -1,27 +1,42 @@
-use warp::{reject::Rejection, Reply, reply::with_status, hyper::StatusCode};
+use warp::{
+    hyper::{header::RETRY_AFTER, StatusCode},
+    reject::Rejection,
+    reply::{with_header, with_status},
+    Reply,
+};
 
-use super::{repository::Error, account};
+use super::{repository::Error, account, common, oidc};
 
 // all postgres errors should be treated as string.
 // that is lucky that the 23 category is only numbers
 // but see this example: 42P01	UNDEFINED TABLE;
 const DUPLICATE_KEY: &str = "23505";
+// a hash takes a fraction of a second, the queue should have moved by then
+const HASHING_RETRY_AFTER_SECONDS: u64 = 1;
 
 pub async fn return_custom_error(r: Rejection) -> Result<impl Reply, Rejection> {
+    let reply = error_reply(&r)?;
+
+    // throttled requests also tell the client when to come back
+    match r.find() {
+        Some(account::Error::TooManyAttempts(retry_after)) => {
+            Ok(with_header(reply, RETRY_AFTER, retry_after.to_string()).into_response())
+        }
+        Some(account::Error::HashingBusy) => {
+            Ok(with_header(reply, RETRY_AFTER, HASHING_RETRY_AFTER_SECONDS.to_string()).into_response())
+        }
+        _ => Ok(reply.into_response()),
+    }
+}
+
+fn error_reply(r: &Rejection) -> Result<impl Reply, Rejection> {
     if let Some(Error::DatabaseQueryError(e)) = r.find() {
         match e {
-            sqlx::Error::Database(err) => {
-                if err.code().unwrap() == DUPLICATE_KEY {
-                    Ok(with_status(
-                        "Entry already exists",
-                        StatusCode::UNPROCESSABLE_ENTITY
-                    ))
-                } else {
-                    Ok(with_status(
-                        "Cannot process data: {err}",
-                        StatusCode::UNPROCESSABLE_ENTITY
-                    ))
-                }
+            sqlx::Error::Database(err) if err.code().as_deref() == Some(DUPLICATE_KEY) => {
+                Ok(with_status(
+                    "Entry already exists",
+                    StatusCode::UNPROCESSABLE_ENTITY
+                ))
             }
             sqlx::Error::RowNotFound => {
                 Ok(with_status("Entry not found", StatusCode::NOT_FOUND))
@@ -36,8 +51,102 @@ pub async fn return_custom_error(r: Rejection) -> Result<impl Reply, Rejection>
     // custom return error when user gives a wrong credentials
     } else if let Some(account::Error::WrongCredentials) = r.find() {
         Ok(with_status("invalid credentials", StatusCode::UNAUTHORIZED))
+    } else if let Some(account::Error::TooManyAttempts(_)) = r.find() {
+        Ok(with_status("too many failed attempts, try again later", StatusCode::TOO_MANY_REQUESTS))
+    } else if let Some(account::Error::HashingBusy) = r.find() {
+        Ok(with_status("server busy, try again later", StatusCode::SERVICE_UNAVAILABLE))
+    // the database is failing, telling the client to log in again won't help
+    } else if let Some(err @ account::Error::SessionLookupFailed(_)) = r.find() {
+        eprintln!("{}", err);
+        Ok(with_status("server unavailable, try again later", StatusCode::SERVICE_UNAVAILABLE))
+    // a password couldn't be hashed or checked, nothing the client can fix
+    } else if let Some(err @ account::Error::ArgonLibraryError(_)) = r.find() {
+        eprintln!("{}", err);
+        Ok(with_status("internal server error", StatusCode::INTERNAL_SERVER_ERROR))
+    // no usable token: the client has to authenticate (again)
+    } else if let Some(account::Error::Unauthenticated | account::Error::CannotDecryptToken) = r.find() {
+        Ok(with_status("you are not authenticated", StatusCode::UNAUTHORIZED))
+    // authenticated, but the session's role or ownership doesn't allow it
     } else if let Some(account::Error::Unauthorized) = r.find() {
-        Ok(with_status("you are not authorised", StatusCode::UNAUTHORIZED))
+        Ok(with_status("you are not authorised", StatusCode::FORBIDDEN))
+    // both cases force the client back to /login; a reused token has also
+    // revoked every token rotated from the same login.
+    } else if let Some(account::Error::InvalidRefreshToken | account::Error::RefreshTokenReused) = r.find() {
+        Ok(with_status("invalid refresh token", StatusCode::UNAUTHORIZED))
+    } else if let Some(account::Error::InvalidResetToken) = r.find() {
+        Ok(with_status("invalid or expired reset token", StatusCode::BAD_REQUEST))
+    } else if let Some(account::Error::InvalidEmail) = r.find() {
+        Ok(with_status("invalid email address", StatusCode::BAD_REQUEST))
+    } else if let Some(account::Error::InvalidVerificationToken) = r.find() {
+        Ok(with_status("invalid or expired verification token", StatusCode::BAD_REQUEST))
+    } else if let Some(account::Error::EmailNotVerified) = r.find() {
+        Ok(with_status("verify your email address first", StatusCode::FORBIDDEN))
+    } else if let Some(account::Error::TwoFactorAlreadyEnabled) = r.find() {
+        Ok(with_status("two-factor authentication is already enabled", StatusCode::CONFLICT))
+    } else if let Some(account::Error::TwoFactorNotEnrolled) = r.find() {
+        Ok(with_status("two-factor authentication is not set up", StatusCode::CONFLICT))
+    } else if let Some(account::Error::InvalidTwoFactorCode) = r.find() {
+        Ok(with_status("invalid two-factor code", StatusCode::UNAUTHORIZED))
+    // the challenge expired, the client has to start again from /login
+    } else if let Some(account::Error::InvalidChallenge) = r.find() {
+        Ok(with_status("invalid or expired login challenge", StatusCode::UNAUTHORIZED))
+    } else if let Some(account::Error::RecentLoginRequired) = r.find() {
+        Ok(with_status(
+            "this account has no password: log in through your identity provider again and retry \
+             within 10 minutes, or set a password with a password reset",
+            StatusCode::FORBIDDEN
+        ))
+    // api keys and scoped tokens only reach what they were granted
+    } else if let Some(account::Error::InsufficientScope) = r.find() {
+        Ok(with_status("insufficient scope", StatusCode::FORBIDDEN))
+    } else if let Some(account::Error::InvalidScopes) = r.find() {
+        Ok(with_status("at least one scope is required", StatusCode::BAD_REQUEST))
+    } else if let Some(account::Error::InvalidApiKey) = r.find() {
+        Ok(with_status(
+            "api key name is limited to 64 characters and the expiry to 1 to 3650 days",
+            StatusCode::BAD_REQUEST
+        ))
+    } else if let Some(account::Error::InvalidProfile) = r.find() {
+        Ok(with_status(
+            "display name is limited to 64 characters, bio to 1000 and avatar url must be http(s)",
+            StatusCode::BAD_REQUEST
+        ))
+    } else if let Some(account::Error::InsufficientReputation(_)) = r.find() {
+        Ok(with_status("not enough reputation", StatusCode::FORBIDDEN))
+    } else if let Some(common::Error::InvalidSort) = r.find() {
+        Ok(with_status("sort must be one of newest, oldest or score", StatusCode::BAD_REQUEST))
+    } else if let Some(common::Error::InvalidInclude) = r.find() {
+        Ok(with_status("include can only list answers and author", StatusCode::BAD_REQUEST))
+    } else if let Some(common::Error::OwnContentVote) = r.find() {
+        Ok(with_status("you can't vote on your own content", StatusCode::FORBIDDEN))
+    } else if let Some(common::Error::InvalidComment) = r.find() {
+        Ok(with_status("comments can't be blank and are limited to 600 characters", StatusCode::BAD_REQUEST))
+    } else if let Some(err) = r.find::<oidc::Error>() {
+        match err {
+            oidc::Error::NotConfigured => {
+                Ok(with_status("OpenID Connect login is not enabled", StatusCode::NOT_FOUND))
+            }
+            oidc::Error::InvalidState => {
+                Ok(with_status("invalid or expired login state", StatusCode::BAD_REQUEST))
+            }
+            oidc::Error::BrowserMismatch => {
+                Ok(with_status("login was started in another browser, start it again", StatusCode::BAD_REQUEST))
+            }
+            oidc::Error::EmailInUse => {
+                Ok(with_status(
+                    "this email is already registered, log in with your password",
+                    StatusCode::CONFLICT
+                ))
+            }
+            oidc::Error::LoginRefused(_) | oidc::Error::InvalidIdToken(_) => {
+                Ok(with_status("login rejected by the identity provider", StatusCode::UNAUTHORIZED))
+            }
+            // nothing the client can fix, but worth a look on our side
+            oidc::Error::ProviderUnreachable(_) | oidc::Error::InvalidProviderResponse(_) => {
+                eprintln!("{}", err);
+                Ok(with_status("identity provider unavailable", StatusCode::BAD_GATEWAY))
+            }
+        }
     } else {
 
         if r.is_not_found() {
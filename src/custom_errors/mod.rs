@@ -2,3 +2,4 @@ pub mod repository;
 pub mod account;
 pub mod custom_error_recover;
 pub mod common;
+pub mod oidc;
\ No newline at end of file
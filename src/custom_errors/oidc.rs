@@ -0,0 +1,35 @@
+use std::fmt::Display;
+
+use warp::reject::Reject;
+
+#[derive(Debug)]
+pub enum Error {
+    NotConfigured,
+    InvalidState,
+    // the callback didn't come back to the browser that started the login
+    BrowserMismatch,
+    // the provider answered the authorization request with an error
+    LoginRefused(String),
+    ProviderUnreachable(reqwest::Error),
+    InvalidProviderResponse(String),
+    InvalidIdToken(&'static str),
+    // the provider didn't verify an email a local account already uses
+    EmailInUse,
+}
+
+impl Reject for Error {}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::NotConfigured => write!(f, "no OpenID Connect provider is configured"),
+            Error::InvalidState => write!(f, "login state is unknown, used or expired"),
+            Error::BrowserMismatch => write!(f, "login was started in another browser"),
+            Error::LoginRefused(err) => write!(f, "identity provider refused the login: {}", err),
+            Error::ProviderUnreachable(err) => write!(f, "cannot reach the identity provider: {}", err),
+            Error::InvalidProviderResponse(err) => write!(f, "unexpected identity provider response: {}", err),
+            Error::InvalidIdToken(reason) => write!(f, "id token rejected: {}", reason),
+            Error::EmailInUse => write!(f, "unverified email already belongs to an account"),
+        }
+    }
+}
@@ -12,7 +12,7 @@ impl Reject for Error {}
 
 impl Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match &*self {
+        match self {
             Error::DatabaseQueryError(err) => write!(f, "Database query error: {}", err),
             Error::MigrationError(err) => write!(f, "Database migration error: {}", err),
         }
@@ -0,0 +1,184 @@
+use std::sync::Arc;
+
+use rand::Rng;
+use tokio::sync::Semaphore;
+
+use crate::{
+    config::{Argon2Params, Config},
+    custom_errors::account::Error,
+    models::account::PasswordHash,
+};
+
+// argon2 is deliberately slow and would block the executor thread it runs on,
+// so hashes are computed on tokio's blocking pool. At most `workers` run at
+// once and `queue` more may wait for a turn; past that requests are turned
+// away with a 503 rather than piling up behind each other.
+#[derive(Debug)]
+pub struct PasswordHasher {
+    params: Argon2Params,
+    workers: Arc<Semaphore>,
+    // running plus waiting
+    admission: Arc<Semaphore>,
+}
+
+impl PasswordHasher {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            params: config.argon2,
+            workers: Arc::new(Semaphore::new(config.hashing_workers.max(1))),
+            admission: Arc::new(Semaphore::new(
+                config.hashing_workers.max(1) + config.hashing_queue,
+            )),
+        }
+    }
+
+    pub async fn hash(&self, password: &[u8]) -> Result<PasswordHash, Error> {
+        let params = self.params;
+        let password = password.to_vec();
+        self.run(move || hash_password(&password, &params)).await?
+    }
+
+    pub async fn verify(&self, hash: &PasswordHash, password: &[u8]) -> Result<bool, Error> {
+        let hash = hash.clone();
+        let password = password.to_vec();
+        self.run(move || argon2::verify_encoded(hash.as_str(), &password))
+            .await?
+            .map_err(Error::ArgonLibraryError)
+    }
+
+    // true when `hash` is cheaper to crack than what would be produced now,
+    // going by its encoded parameters: $argon2id$v=19$m=19456,t=2,p=1$salt$hash.
+    // Hashes from before version 19 have no v= field.
+    pub fn needs_rehash(&self, hash: &PasswordHash) -> bool {
+        let mut fields = hash.as_str().split('$').skip(1);
+        let variant = fields.next();
+        let (version, costs) = match fields.next() {
+            Some(version) if version.starts_with("v=") => {
+                (version.trim_start_matches("v="), fields.next())
+            }
+            costs => ("16", costs),
+        };
+
+        let mut mem_cost = 0;
+        let mut time_cost = 0;
+        let mut lanes = 0;
+        for cost in costs.unwrap_or_default().split(',') {
+            match cost.split_once('=') {
+                Some(("m", value)) => mem_cost = value.parse().unwrap_or(0),
+                Some(("t", value)) => time_cost = value.parse().unwrap_or(0),
+                Some(("p", value)) => lanes = value.parse().unwrap_or(0),
+                _ => {}
+            }
+        }
+
+        variant != Some(self.params.variant.as_lowercase_str())
+            || version != argon2::Version::Version13.as_u32().to_string()
+            || mem_cost < self.params.mem_cost
+            || time_cost < self.params.time_cost
+            || lanes < self.params.lanes
+    }
+
+    // the permits move into the blocking task, so a request that goes away
+    // doesn't free its worker before the hash it started is done.
+    async fn run<T, F>(&self, f: F) -> Result<T, Error>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> T + Send + 'static,
+    {
+        let admitted = Arc::clone(&self.admission)
+            .try_acquire_owned()
+            .map_err(|_| Error::HashingBusy)?;
+        // never closed, acquiring can't fail
+        let worker = Arc::clone(&self.workers)
+            .acquire_owned()
+            .await
+            .expect("hashing semaphore closed");
+
+        tokio::task::spawn_blocking(move || {
+            let result = f();
+            drop((worker, admitted));
+            result
+        })
+        .await
+        .map_err(|e| std::panic::resume_unwind(e.into_panic()))
+    }
+}
+
+// blocks for as long as the hash takes. Outside of startup go through
+// `PasswordHasher::hash`, which keeps it off the executor.
+pub fn hash_password(password: &[u8], params: &Argon2Params) -> Result<PasswordHash, Error> {
+    let salt = rand::thread_rng().gen::<[u8; 32]>();
+    let config = argon2::Config {
+        variant: params.variant,
+        mem_cost: params.mem_cost,
+        time_cost: params.time_cost,
+        lanes: params.lanes,
+        ..argon2::Config::default()
+    };
+    argon2::hash_encoded(password, &salt, &config)
+        .map(PasswordHash::new)
+        .map_err(Error::ArgonLibraryError)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PARAMS: Argon2Params = Argon2Params {
+        variant: argon2::Variant::Argon2id,
+        mem_cost: 256,
+        time_cost: 2,
+        lanes: 2,
+    };
+
+    fn hasher() -> PasswordHasher {
+        PasswordHasher {
+            params: PARAMS,
+            workers: Arc::new(Semaphore::new(1)),
+            admission: Arc::new(Semaphore::new(1)),
+        }
+    }
+
+    fn hash_with(params: Argon2Params) -> PasswordHash {
+        hash_password(b"password", &params).unwrap()
+    }
+
+    #[test]
+    fn current_or_stronger_parameters_are_kept() {
+        let hasher = hasher();
+        assert!(!hasher.needs_rehash(&hash_with(PARAMS)));
+        assert!(!hasher.needs_rehash(&hash_with(Argon2Params { mem_cost: 512, time_cost: 3, lanes: 4, ..PARAMS })));
+    }
+
+    #[test]
+    fn weaker_parameters_are_rehashed() {
+        let hasher = hasher();
+        assert!(hasher.needs_rehash(&hash_with(Argon2Params { mem_cost: 128, ..PARAMS })));
+        assert!(hasher.needs_rehash(&hash_with(Argon2Params { time_cost: 1, ..PARAMS })));
+        assert!(hasher.needs_rehash(&hash_with(Argon2Params { lanes: 1, ..PARAMS })));
+    }
+
+    #[test]
+    fn other_variants_and_versions_are_rehashed() {
+        let hasher = hasher();
+        assert!(hasher.needs_rehash(&hash_with(Argon2Params { variant: argon2::Variant::Argon2i, ..PARAMS })));
+        // version 16 hashes have no v= field
+        let current = hash_with(PARAMS);
+        let version_16 = current.as_str().replacen("$v=19", "", 1);
+        assert!(hasher.needs_rehash(&PasswordHash::new(version_16)));
+    }
+
+    #[test]
+    fn malformed_hashes_are_rehashed() {
+        let hasher = hasher();
+        for hash in [
+            "",
+            "not a hash",
+            "$argon2id$v=19",
+            "$argon2id$v=19$m=abc,t=2,p=2$c2FsdA$aGFzaA",
+            "$argon2id$v=19$t=2,p=2$c2FsdA$aGFzaA",
+        ] {
+            assert!(hasher.needs_rehash(&PasswordHash::new(hash.to_string())), "{}", hash);
+        }
+    }
+}
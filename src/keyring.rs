@@ -0,0 +1,159 @@
+use data_encoding::BASE64URL_NOPAD;
+use ring::signature::{Ed25519KeyPair, KeyPair};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::config::{Config, PasetoKey, TokenMode};
+
+#[derive(Debug, Deserialize)]
+struct Footer {
+    kid: String,
+}
+
+// a verification key as published on /.well-known/paseto-keys
+#[derive(Debug, Serialize)]
+pub struct PublicKeyView {
+    pub kid: String,
+    pub version: &'static str,
+    pub purpose: &'static str,
+    // base64url, without padding
+    pub public_key: String,
+}
+
+#[derive(Debug)]
+struct SigningKey {
+    id: String,
+    key_pair: Ed25519KeyPair,
+    footer: String,
+}
+
+// every paseto key the api knows about. New tokens are encrypted with the
+// current key; retired keys keep older tokens valid until they expire.
+// In public mode sessions are signed instead, other tokens stay local.
+#[derive(Debug)]
+pub struct Keyring {
+    current: PasetoKey,
+    footer: String,
+    retired: Vec<PasetoKey>,
+    signing: Option<SigningKey>,
+    retired_public: Vec<PasetoKey>,
+}
+
+impl Keyring {
+    pub fn new(config: &Config) -> Self {
+        let signing = match config.token_mode {
+            TokenMode::Public => config.paseto_signing_key.as_ref().map(|key| SigningKey {
+                id: key.id.clone(),
+                // the seed length was checked when loading the config
+                key_pair: Ed25519KeyPair::from_seed_unchecked(&key.key)
+                    .expect("ed25519 seed is 32 bytes"),
+                footer: footer(&key.id),
+            }),
+            TokenMode::Local => None,
+        };
+
+        Keyring {
+            current: config.paseto_key.clone(),
+            footer: footer(&config.paseto_key.id),
+            retired: config.retired_paseto_keys.clone(),
+            signing,
+            retired_public: config.retired_paseto_public_keys.clone(),
+        }
+    }
+
+    pub fn encryption_key(&self) -> &[u8] {
+        &self.current.key
+    }
+
+    // to be set on every local token, see `validate`
+    pub fn footer(&self) -> &str {
+        &self.footer
+    }
+
+    // the key and footer for session tokens, when they are signed
+    pub fn signing_key(&self) -> Option<(&Ed25519KeyPair, &str)> {
+        self.signing
+            .as_ref()
+            .map(|signing| (&signing.key_pair, signing.footer.as_str()))
+    }
+
+    pub fn public_keys(&self) -> Vec<PublicKeyView> {
+        let current = self
+            .signing
+            .iter()
+            .map(|signing| (signing.id.as_str(), signing.key_pair.public_key().as_ref()));
+        let retired = self
+            .retired_public
+            .iter()
+            .map(|key| (key.id.as_str(), key.key.as_slice()));
+
+        current
+            .chain(retired)
+            .map(|(kid, public_key)| PublicKeyView {
+                kid: kid.to_string(),
+                version: "v2",
+                purpose: "public",
+                public_key: BASE64URL_NOPAD.encode(public_key),
+            })
+            .collect()
+    }
+
+    // decrypts or verifies `token` with the key named in its footer.
+    // Tokens issued before key ids existed have no footer and can only
+    // have been made with the current key.
+    pub fn validate(&self, token: &str) -> Option<Value> {
+        let footer = match token.split('.').nth(3) {
+            Some(encoded) => Some(
+                String::from_utf8(BASE64URL_NOPAD.decode(encoded.as_bytes()).ok()?).ok()?,
+            ),
+            None => None,
+        };
+        let kid = match &footer {
+            Some(footer) => Some(serde_json::from_str::<Footer>(footer).ok()?.kid),
+            None => None,
+        };
+
+        if token.starts_with("v2.public.") {
+            let signing = self.signing.as_ref();
+            let public_key = match &kid {
+                Some(kid) => signing
+                    .filter(|signing| &signing.id == kid)
+                    .map(|signing| signing.key_pair.public_key().as_ref())
+                    .or_else(|| {
+                        self.retired_public
+                            .iter()
+                            .find(|key| &key.id == kid)
+                            .map(|key| key.key.as_slice())
+                    })?,
+                None => signing?.key_pair.public_key().as_ref(),
+            };
+
+            return paseto::tokens::validate_public_token(
+                token,
+                footer.as_deref(),
+                &paseto::tokens::PasetoPublicKey::ED25519PublicKey(public_key),
+                &paseto::tokens::TimeBackend::Chrono,
+            )
+            .ok();
+        }
+
+        let key = match &kid {
+            Some(kid) => std::iter::once(&self.current)
+                .chain(self.retired.iter())
+                .find(|key| &key.id == kid)?,
+            None => &self.current,
+        };
+
+        paseto::tokens::validate_local_token(
+            token,
+            footer.as_deref(),
+            &key.key,
+            &paseto::tokens::TimeBackend::Chrono,
+        )
+        .ok()
+    }
+}
+
+fn footer(kid: &str) -> String {
+    serde_json::json!({ "kid": kid }).to_string()
+}
@@ -1,18 +1,46 @@
+// controllers deliberately return `impl Future + '_` instead of using `async fn`,
+// see the note on `AuthenticationController::register_account`.
+#![allow(clippy::manual_async_fn)]
+// the routes are chained into a single filter type, deep enough to need it
+#![recursion_limit = "256"]
+
 use std::sync::Arc;
 
 use config::Config;
 use controllers::{
-    answer::AnswerController, authentication::AuthenticationController,
+    account::AccountController, answer::AnswerController,
+    authentication::AuthenticationController, comment::CommentController,
     question::QuestionController,
 };
 use custom_errors::{custom_error_recover::return_custom_error, repository::Error};
 use repository::Repository;
+use revocation::RevocationStore;
+use throttle::LoginThrottle;
 use routes::{
-    answer::{create_answer_route, update_answer_route},
-    authentication::{login_route, registration_route},
+    account::{
+        export_account_route, get_accounts_route, get_own_profile_route, get_public_profile_route,
+        replay_reputation_route, update_account_role_route, update_own_profile_route,
+    },
+    answer::{
+        create_answer_route, delete_answer_route, get_answer_route, get_answers_route,
+        unvote_answer_route, update_answer_route, vote_answer_route,
+    },
+    authentication::{
+        change_password_route, confirm_two_factor_route, create_api_key_route,
+        delete_account_route, delete_session_route, disable_two_factor_route, enroll_two_factor_route,
+        get_api_keys_route, get_sessions_route, login_route,
+        logout_route, oidc_callback_route, oidc_login_route, paseto_keys_route, password_reset_confirm_route, password_reset_route, refresh_token_route,
+        registration_route, resend_verification_route, revoke_api_key_route, scoped_token_route, two_factor_login_route,
+        verify_email_route,
+    },
+    comment::{
+        create_answer_comment_route, create_question_comment_route, delete_comment_route,
+        update_comment_route,
+    },
     question::{
-        add_question_route, delete_question_route, get_question_route, get_questions_route,
-        update_question_route,
+        accept_answer_route, add_question_route, delete_question_route, get_question_route,
+        get_questions_route, unaccept_answer_route, unvote_question_route, update_question_route,
+        vote_question_route,
     },
 };
 use tokio::sync::oneshot::{Sender, self};
@@ -21,23 +49,34 @@ use warp::{Filter, Reply};
 mod models;
 mod routes;
 mod controllers {
+    pub mod account;
     pub mod answer;
     pub mod authentication;
+    pub mod comment;
     pub mod question;
 }
 
 mod repository {
     pub mod database_repository;
+    #[allow(clippy::module_inception)]
     pub mod repository;
     pub use repository::Repository;
 }
 
 mod custom_errors;
+mod crypto;
+mod hasher;
+mod mailer;
+mod oidc;
+mod revocation;
+mod throttle;
+mod keyring;
+mod totp;
 
 pub mod config;
 
 pub async fn run(config: &Config, repository: Arc<Repository>) {
-    let routes = build_routes(Arc::clone(&repository)).await;
+    let routes = build_routes(config, Arc::clone(&repository)).await;
 
     warp::serve(routes)
         .run(([127, 0, 0, 1], config.api_port))
@@ -50,34 +89,95 @@ pub async fn setup_repository(config: &Config) -> Result<Arc<Repository>, Error>
         config.db_user, config.db_password, config.db_host, config.db_port, config.db_name
     ))
     .await
-    .map_err(|e| Error::DatabaseQueryError(e))?;
+    .map_err(Error::DatabaseQueryError)?;
 
     sqlx::migrate!()
         .run(
             &repository.clone().db_pool
         )
         .await
-        .map_err(|e| Error::MigrationError(e))?;
+        .map_err(Error::MigrationError)?;
 
     Ok(Arc::new(repository))
 }
 
-async fn build_routes(repository: Arc<Repository>) -> impl Filter<Extract = (impl Reply,)> + Clone {
+async fn build_routes(
+    config: &Config,
+    repository: Arc<Repository>,
+) -> impl Filter<Extract = (impl Reply,)> + Clone {
     let cors = warp::cors().allow_any_origin().allow_methods(vec!["POST"]);
 
-    let auth_controller = Arc::new(AuthenticationController::new(Arc::clone(&repository)));
-    let question_controller = Arc::new(QuestionController::new(Arc::clone(&repository)));
-    let answer_controller = Arc::new(AnswerController::new(Arc::clone(&repository)));
+    let revocations = Arc::new(RevocationStore::new(Arc::clone(&repository)));
+    if let Err(e) = revocations.sync().await {
+        eprintln!("cannot load revoked tokens: {}", e);
+    }
+    RevocationStore::spawn_sync(Arc::clone(&revocations));
+
+    let throttle = Arc::new(LoginThrottle::new(config));
+    LoginThrottle::spawn_purge(Arc::clone(&throttle));
+
+    let auth_controller = Arc::new(AuthenticationController::new(
+        Arc::clone(&repository),
+        revocations,
+        mailer::from_config(config),
+        throttle,
+        config,
+    ));
+    let question_controller = Arc::new(QuestionController::new(Arc::clone(&repository), config));
+    let answer_controller = Arc::new(AnswerController::new(Arc::clone(&repository), config));
+    let account_controller = Arc::new(AccountController::new(Arc::clone(&repository), config));
+    let comment_controller = Arc::new(CommentController::new(Arc::clone(&repository), config));
 
     login_route(Arc::clone(&auth_controller))
+        .or(two_factor_login_route(Arc::clone(&auth_controller)))
         .or(registration_route(Arc::clone(&auth_controller)))
-        .or(add_question_route(Arc::clone(&question_controller)))
+        .or(verify_email_route(Arc::clone(&auth_controller)))
+        .or(resend_verification_route(Arc::clone(&auth_controller)))
+        .or(refresh_token_route(Arc::clone(&auth_controller)))
+        .or(scoped_token_route(Arc::clone(&auth_controller)))
+        .or(logout_route(Arc::clone(&auth_controller)))
+        .or(change_password_route(Arc::clone(&auth_controller)))
+        .or(delete_account_route(Arc::clone(&auth_controller)))
+        .or(password_reset_route(Arc::clone(&auth_controller)))
+        .or(password_reset_confirm_route(Arc::clone(&auth_controller)))
+        .or(enroll_two_factor_route(Arc::clone(&auth_controller)))
+        .or(confirm_two_factor_route(Arc::clone(&auth_controller)))
+        .or(disable_two_factor_route(Arc::clone(&auth_controller)))
+        .or(create_api_key_route(Arc::clone(&auth_controller)))
+        .or(get_api_keys_route(Arc::clone(&auth_controller)))
+        .or(revoke_api_key_route(Arc::clone(&auth_controller)))
+        .or(get_sessions_route(Arc::clone(&auth_controller)))
+        .or(delete_session_route(Arc::clone(&auth_controller)))
+        .or(paseto_keys_route(Arc::clone(&auth_controller)))
+        .or(oidc_login_route(Arc::clone(&auth_controller)))
+        .or(oidc_callback_route(Arc::clone(&auth_controller)))
+        .or(add_question_route(Arc::clone(&question_controller), Arc::clone(&auth_controller)))
         .or(get_question_route(Arc::clone(&question_controller)))
         .or(get_questions_route(Arc::clone(&question_controller)))
-        .or(update_question_route(Arc::clone(&question_controller)))
-        .or(delete_question_route(Arc::clone(&question_controller)))
-        .or(create_answer_route(Arc::clone(&answer_controller)))
-        .or(update_answer_route(Arc::clone(&answer_controller)))
+        .or(update_question_route(Arc::clone(&question_controller), Arc::clone(&auth_controller)))
+        .or(delete_question_route(Arc::clone(&question_controller), Arc::clone(&auth_controller)))
+        .or(accept_answer_route(Arc::clone(&question_controller), Arc::clone(&auth_controller)))
+        .or(unaccept_answer_route(Arc::clone(&question_controller), Arc::clone(&auth_controller)))
+        .or(vote_question_route(Arc::clone(&question_controller), Arc::clone(&auth_controller)))
+        .or(unvote_question_route(Arc::clone(&question_controller), Arc::clone(&auth_controller)))
+        .or(create_answer_route(Arc::clone(&answer_controller), Arc::clone(&auth_controller)))
+        .or(update_answer_route(Arc::clone(&answer_controller), Arc::clone(&auth_controller)))
+        .or(delete_answer_route(Arc::clone(&answer_controller), Arc::clone(&auth_controller)))
+        .or(get_answer_route(Arc::clone(&answer_controller)))
+        .or(get_answers_route(Arc::clone(&answer_controller)))
+        .or(vote_answer_route(Arc::clone(&answer_controller), Arc::clone(&auth_controller)))
+        .or(unvote_answer_route(Arc::clone(&answer_controller), Arc::clone(&auth_controller)))
+        .or(create_question_comment_route(Arc::clone(&comment_controller), Arc::clone(&auth_controller)))
+        .or(create_answer_comment_route(Arc::clone(&comment_controller), Arc::clone(&auth_controller)))
+        .or(update_comment_route(Arc::clone(&comment_controller), Arc::clone(&auth_controller)))
+        .or(delete_comment_route(Arc::clone(&comment_controller), Arc::clone(&auth_controller)))
+        .or(get_own_profile_route(Arc::clone(&account_controller), Arc::clone(&auth_controller)))
+        .or(update_own_profile_route(Arc::clone(&account_controller), Arc::clone(&auth_controller)))
+        .or(export_account_route(Arc::clone(&account_controller), Arc::clone(&auth_controller)))
+        .or(get_public_profile_route(Arc::clone(&account_controller)))
+        .or(get_accounts_route(Arc::clone(&account_controller), Arc::clone(&auth_controller)))
+        .or(update_account_role_route(Arc::clone(&account_controller), Arc::clone(&auth_controller)))
+        .or(replay_reputation_route(Arc::clone(&account_controller), Arc::clone(&auth_controller)))
         .with(cors)
         .recover(return_custom_error)
 }
@@ -86,15 +186,12 @@ pub struct OneshotHandler {
     pub sender: Sender<i32>,
 }
 
-pub async fn oneshot(repository: Arc<Repository>) -> OneshotHandler {
-    let routes = build_routes(repository).await;
+pub async fn oneshot(config: &Config, repository: Arc<Repository>) -> OneshotHandler {
+    let routes = build_routes(config, repository).await;
 
     let (tx, rx) = oneshot::channel::<i32>();
 
-    let socket: std::net::SocketAddr = "127.0.0.1:8080"
-        .to_string()
-        .parse()
-        .expect("address not valid.");
+    let socket: std::net::SocketAddr = ([127, 0, 0, 1], config.api_port).into();
 
     let (_, server) = warp::serve(routes).bind_with_graceful_shutdown(socket, async {
         rx.await.ok();
@@ -0,0 +1,65 @@
+use std::{fmt::Debug, io, path::PathBuf, sync::Arc};
+
+use async_trait::async_trait;
+use chrono::Utc;
+
+use crate::config::{Config, MailerKind};
+
+#[derive(Debug, Clone)]
+pub struct Mail {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+// how account emails (password resets and the like) leave the api.
+// The implementations here are meant for local development and tests.
+#[async_trait]
+pub trait Mailer: Debug + Send + Sync {
+    async fn send(&self, mail: Mail) -> Result<(), io::Error>;
+}
+
+#[derive(Debug)]
+pub struct StdoutMailer;
+
+#[async_trait]
+impl Mailer for StdoutMailer {
+    async fn send(&self, mail: Mail) -> Result<(), io::Error> {
+        println!("To: {}\nSubject: {}\n\n{}\n", mail.to, mail.subject, mail.body);
+        Ok(())
+    }
+}
+
+// drops every mail as a file in `directory`, one file per mail.
+#[derive(Debug)]
+pub struct FileMailer {
+    pub directory: PathBuf,
+}
+
+#[async_trait]
+impl Mailer for FileMailer {
+    async fn send(&self, mail: Mail) -> Result<(), io::Error> {
+        tokio::fs::create_dir_all(&self.directory).await?;
+
+        let file_name = format!(
+            "{}-{}.eml",
+            Utc::now().format("%Y%m%d%H%M%S%f"),
+            mail.to.replace(|c: char| !c.is_ascii_alphanumeric() && c != '@' && c != '.', "_")
+        );
+
+        tokio::fs::write(
+            self.directory.join(file_name),
+            format!("To: {}\nSubject: {}\n\n{}\n", mail.to, mail.subject, mail.body),
+        )
+        .await
+    }
+}
+
+pub fn from_config(config: &Config) -> Arc<dyn Mailer> {
+    match config.mailer {
+        MailerKind::Stdout => Arc::new(StdoutMailer),
+        MailerKind::File => Arc::new(FileMailer {
+            directory: PathBuf::from(&config.mail_drop_dir),
+        }),
+    }
+}
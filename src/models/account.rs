@@ -1,19 +1,199 @@
+use std::fmt::Display;
+
 use chrono::{Utc, DateTime};
 use serde::{Serialize, Deserialize};
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+use super::{api_key::ApiKeyId, scope::Scope};
+
+// an account as stored in the database. It is deliberately neither Serialize
+// nor Deserialize: requests come in as RegistrationRequest/LoginRequest and
+// responses go out as AccountView, so the password hash can't end up in a body.
+#[derive(Debug, Clone)]
 pub struct Account {
-    pub id: Option<AccountId>,
+    pub id: AccountId,
+    pub email: String,
+    pub password: PasswordHash,
+    pub role: Role,
+    pub two_factor_enabled: bool,
+    // false for accounts created by an oidc login, until a password reset
+    pub has_password: bool,
+}
+
+// an account about to be inserted, its password already hashed.
+#[derive(Debug)]
+pub struct NewAccount {
+    pub email: String,
+    pub password: PasswordHash,
+}
+
+// an argon2 encoded hash. No Serialize impl and a redacted Debug impl, so it
+// doesn't leak through a json reply or a log line.
+#[derive(Clone)]
+pub struct PasswordHash(String);
+
+impl PasswordHash {
+    pub fn new(hash: String) -> Self {
+        Self(hash)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Debug for PasswordHash {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "PasswordHash(<redacted>)")
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RegistrationRequest {
     pub email: String,
     pub password: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct LoginRequest {
+    pub email: String,
+    pub password: String,
+}
+
+// what is returned when an account is listed, without the password hash.
+#[derive(Debug, Serialize)]
+pub struct AccountView {
+    pub id: AccountId,
+    pub email: String,
+    pub role: Role,
+    pub reputation: i32,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct AccountId(pub i32);
 
+// the "deleted user" placeholder created by a migration, see config::AccountDeletion
+pub const DELETED_ACCOUNT_ID: i32 = 0;
+
+// deliberately loose, the verification email is what proves the address works.
+// It only rejects what can't possibly be delivered.
+pub fn is_valid_email(email: &str) -> bool {
+    if email.len() > 255 || email.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        return false;
+    }
+
+    match email.split_once('@') {
+        Some((local, domain)) => {
+            !local.is_empty()
+                && !domain.contains('@')
+                && domain.contains('.')
+                && !domain.starts_with('.')
+                && !domain.ends_with('.')
+                && !domain.contains("..")
+        }
+        None => false,
+    }
+}
+
+// ordered by privilege, an admin can do everything a moderator can.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    #[default]
+    User,
+    Moderator,
+    Admin,
+}
+
+impl Role {
+    pub fn can_moderate(&self) -> bool {
+        *self >= Role::Moderator
+    }
+}
+
+impl Display for Role {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Role::User => write!(f, "user"),
+            Role::Moderator => write!(f, "moderator"),
+            Role::Admin => write!(f, "admin"),
+        }
+    }
+}
+
+// the accounts table has a check constraint on the role column,
+// anything else can't come back from the database.
+impl From<&str> for Role {
+    fn from(role: &str) -> Self {
+        match role {
+            "admin" => Role::Admin,
+            "moderator" => Role::Moderator,
+            _ => Role::User,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RoleUpdate {
+    pub role: Role,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EmailVerification {
+    pub token: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VerificationResendRequest {
+    pub email: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PasswordChange {
+    pub old_password: String,
+    pub new_password: String,
+}
+
+// the password is asked again, a stolen token alone can't close the account
+#[derive(Debug, Deserialize)]
+pub struct AccountDeletionRequest {
+    // not needed by accounts without a password, see `Account::has_password`
+    pub password: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PasswordResetRequest {
+    pub email: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PasswordResetConfirmation {
+    pub token: String,
+    pub new_password: String,
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Session {
     pub exp: DateTime<Utc>,
     pub account_id: AccountId,
     pub nbf: DateTime<Utc>,
+    // token id, checked against the revocation store
+    pub jti: String,
+    // refresh token family the token was issued from
+    pub sid: String,
+    pub role: Role,
+    // missing for a regular login, which can do anything its role allows.
+    // Scoped tokens and api keys list what they were granted.
+    #[serde(default)]
+    pub scopes: Option<Vec<Scope>>,
+    // set when the session was built from an api key rather than a token
+    #[serde(skip)]
+    pub api_key_id: Option<ApiKeyId>,
+}
+
+impl Session {
+    pub fn has_scope(&self, scope: Scope) -> bool {
+        self.scopes
+            .as_ref()
+            .is_none_or(|scopes| scopes.contains(&scope))
+    }
 }
@@ -1,17 +1,45 @@
 use serde::{Serialize, Deserialize};
 
-use super::question::QuestionId;
+use super::{comment::Comment, profile::Author, question::QuestionId};
 
 #[derive(Debug, Serialize)]
 pub struct Answer {
     pub id: AnswerId,
     pub content: String,
+    // upvotes minus downvotes
+    pub score: i32,
     pub question_id: QuestionId,
+    // marked by the question's author as the one that solved it
+    pub is_accepted: bool,
+    pub author: Option<Author>,
+    // only filled in when the answer comes with its question
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comments: Option<Vec<Comment>>,
 }
 
 #[derive(Debug, Serialize, Clone, Copy)]
 pub struct AnswerId(pub i32);
 
+// order of GET /question/{id}/answers, newest first unless asked otherwise
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AnswerSort {
+    #[default]
+    Newest,
+    Oldest,
+    Score,
+}
+
+impl AnswerSort {
+    pub fn parse(sort: &str) -> Option<Self> {
+        match sort {
+            "newest" => Some(AnswerSort::Newest),
+            "oldest" => Some(AnswerSort::Oldest),
+            "score" => Some(AnswerSort::Score),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct AnswerDTO {
     pub content: String,
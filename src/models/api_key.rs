@@ -0,0 +1,53 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    account::{AccountId, Role},
+    scope::Scope,
+};
+
+pub const API_KEY_NAME_MAX_LENGTH: usize = 64;
+// ten years, longer lived keys should not be needed
+pub const API_KEY_MAX_EXPIRY_DAYS: i64 = 3650;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+pub struct ApiKeyId(pub i32);
+
+// a key as listed to its owner. Only the prefix is kept in clear,
+// to tell keys apart.
+#[derive(Debug, Serialize)]
+pub struct ApiKey {
+    pub id: ApiKeyId,
+    pub name: String,
+    pub prefix: String,
+    pub scopes: Vec<Scope>,
+    pub expires_on: Option<DateTime<Utc>>,
+    pub last_used_on: Option<DateTime<Utc>>,
+    pub created_on: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ApiKeyRequest {
+    pub name: String,
+    // defaults to every scope the creating session has
+    pub scopes: Option<Vec<Scope>>,
+    pub expires_in_days: Option<i64>,
+}
+
+// returned once, on creation: the key itself is never shown again
+#[derive(Debug, Serialize)]
+pub struct CreatedApiKey {
+    pub key: String,
+    #[serde(flatten)]
+    pub api_key: ApiKey,
+}
+
+// what `auth` needs to build a session out of a key
+#[derive(Debug)]
+pub struct ApiKeyOwner {
+    pub id: ApiKeyId,
+    pub account_id: AccountId,
+    pub role: Role,
+    pub scopes: Vec<Scope>,
+    pub expires_on: Option<DateTime<Utc>>,
+}
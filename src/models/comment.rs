@@ -0,0 +1,36 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{answer::AnswerId, profile::Author, question::QuestionId};
+
+pub const COMMENT_MAX_LENGTH: usize = 600;
+
+// a remark on a question or an answer, exactly one of the ids is set
+#[derive(Debug, Serialize)]
+pub struct Comment {
+    pub id: CommentId,
+    pub content: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub question_id: Option<QuestionId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub answer_id: Option<AnswerId>,
+    pub author: Option<Author>,
+    pub created_on: DateTime<Utc>,
+    pub updated_on: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, Clone, Copy)]
+pub struct CommentId(pub i32);
+
+// POST /question/{id}/comments, POST /answer/{id}/comments and PUT /comment/{id}
+#[derive(Debug, Deserialize)]
+pub struct CommentDTO {
+    pub content: String,
+}
+
+impl CommentDTO {
+    pub fn is_valid(&self) -> bool {
+        let content = self.content.trim();
+        !content.is_empty() && content.chars().count() <= COMMENT_MAX_LENGTH
+    }
+}
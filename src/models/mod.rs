@@ -1,4 +1,14 @@
 pub mod account;
+pub mod api_key;
 pub mod question;
 pub mod pagination;
-pub mod answer;
\ No newline at end of file
+pub mod post;
+pub mod answer;
+pub mod comment;
+pub mod oidc;
+pub mod profile;
+pub mod reputation;
+pub mod scope;
+pub mod token;
+pub mod two_factor;
+pub mod vote;
\ No newline at end of file
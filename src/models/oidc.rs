@@ -0,0 +1,26 @@
+use serde::Deserialize;
+
+// query string the identity provider redirects back with
+#[derive(Debug, Deserialize)]
+pub struct OidcCallback {
+    pub state: String,
+    pub code: Option<String>,
+    // set instead of `code` when the provider refused the login
+    pub error: Option<String>,
+}
+
+// kept between /login/oidc and the callback, found again through the state
+#[derive(Debug)]
+pub struct OidcLogin {
+    pub code_verifier: String,
+    pub nonce: String,
+}
+
+// who the identity provider says logged in, taken from a validated id token
+#[derive(Debug, Clone)]
+pub struct ExternalIdentity {
+    pub issuer: String,
+    pub subject: String,
+    pub email: Option<String>,
+    pub email_verified: bool,
+}
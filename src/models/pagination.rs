@@ -16,13 +16,13 @@ pub fn extract_pagination(params: HashMap<String, String>) -> Result<Pagination,
                     .get("limit")
                     .unwrap()
                     .parse::<i16>()
-                    .map_err(|e| Error::ParseError(e))?,
+                    .map_err(Error::Parse)?,
             ),
             offset: params
                 .get("offset")
                 .unwrap()
                 .parse::<i16>()
-                .map_err(|e| Error::ParseError(e))?,
+                .map_err(Error::Parse)?,
         })
     }
     Err(Error::MissingParameters)
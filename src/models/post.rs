@@ -0,0 +1,8 @@
+use super::{answer::AnswerId, question::QuestionId};
+
+// a question or an answer, what votes and comments are attached to
+#[derive(Debug, Clone, Copy)]
+pub enum PostId {
+    Question(QuestionId),
+    Answer(AnswerId),
+}
@@ -0,0 +1,75 @@
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{account::AccountId, answer::Answer, comment::Comment, question::Question};
+
+pub const DISPLAY_NAME_MAX_LENGTH: usize = 64;
+pub const BIO_MAX_LENGTH: usize = 1000;
+pub const AVATAR_URL_MAX_LENGTH: usize = 512;
+
+// the caller's own profile, returned by /account/me
+#[derive(Debug, Serialize)]
+pub struct Profile {
+    pub id: AccountId,
+    pub email: String,
+    pub display_name: Option<String>,
+    pub bio: Option<String>,
+    pub avatar_url: Option<String>,
+    pub reputation: i32,
+    pub created_on: Option<NaiveDateTime>,
+}
+
+// what anybody can see about an account, never the email or the password.
+#[derive(Debug, Serialize)]
+pub struct PublicProfile {
+    pub id: AccountId,
+    pub display_name: Option<String>,
+    pub bio: Option<String>,
+    pub avatar_url: Option<String>,
+    pub reputation: i32,
+    pub created_on: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ProfileUpdate {
+    pub display_name: Option<String>,
+    pub bio: Option<String>,
+    pub avatar_url: Option<String>,
+}
+
+impl ProfileUpdate {
+    pub fn is_valid(&self) -> bool {
+        let display_name = self
+            .display_name
+            .as_ref()
+            .is_none_or(|name| name.chars().count() <= DISPLAY_NAME_MAX_LENGTH);
+        let bio = self
+            .bio
+            .as_ref()
+            .is_none_or(|bio| bio.chars().count() <= BIO_MAX_LENGTH);
+        let avatar_url = self.avatar_url.as_ref().is_none_or(|url| {
+            url.len() <= AVATAR_URL_MAX_LENGTH
+                && (url.starts_with("https://") || url.starts_with("http://"))
+        });
+
+        display_name && bio && avatar_url
+    }
+}
+
+// embedded in questions and answers to show who wrote them
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Author {
+    pub id: AccountId,
+    pub display_name: Option<String>,
+    pub avatar_url: Option<String>,
+}
+
+// everything stored about an account that its owner wrote, from /account/export
+#[derive(Debug, Serialize)]
+pub struct AccountExport {
+    pub account: Profile,
+    pub questions: Vec<Question>,
+    pub answers: Vec<Answer>,
+    pub comments: Vec<Comment>,
+    pub exported_on: DateTime<Utc>,
+}
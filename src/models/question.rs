@@ -2,12 +2,17 @@ use std::fmt::Display;
 
 use serde::{Deserialize, Serialize};
 
+use super::{answer::Answer, comment::Comment, profile::Author};
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct Question {
     pub id: QuestionId,
     pub title: String,
     pub content: String,
     pub tags: Option<Vec<String>>,
+    // upvotes minus downvotes
+    pub score: i32,
+    pub author: Option<Author>,
 }
 
 impl Display for Question {
@@ -20,6 +25,39 @@ impl Display for Question {
     }
 }
 
+// GET /question/{id} with `?include=`, the question and whatever was asked for.
+// The comments on the question and on each answer come with the answers.
+#[derive(Debug, Serialize)]
+pub struct QuestionDetail {
+    #[serde(flatten)]
+    pub question: Question,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub answers: Option<Vec<Answer>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comments: Option<Vec<Comment>>,
+}
+
+// what `?include=answers,author` can embed in a question. Without the
+// parameter the author is embedded, as it always was.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Include {
+    Answers,
+    Author,
+}
+
+impl Include {
+    pub fn parse_list(include: &str) -> Option<Vec<Self>> {
+        include
+            .split(',')
+            .map(|include| match include.trim() {
+                "answers" => Some(Include::Answers),
+                "author" => Some(Include::Author),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 // used to create new questions, deserialising from post;
 pub struct QuestionDTO {
@@ -0,0 +1,24 @@
+// what earns (or costs) reputation, stored as `kind` in reputation_events.
+// What each is worth is configured, see `config::ReputationPoints`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReputationEvent {
+    QuestionAsked,
+    AnswerPosted,
+    // a vote on one of the account's questions or answers
+    Upvoted,
+    Downvoted,
+    // the question's author accepted the account's answer
+    AnswerAccepted,
+}
+
+impl ReputationEvent {
+    pub fn kind(self) -> &'static str {
+        match self {
+            ReputationEvent::QuestionAsked => "question_asked",
+            ReputationEvent::AnswerPosted => "answer_posted",
+            ReputationEvent::Upvoted => "upvoted",
+            ReputationEvent::Downvoted => "downvoted",
+            ReputationEvent::AnswerAccepted => "answer_accepted",
+        }
+    }
+}
@@ -0,0 +1,49 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+// what a credential restricted to some scopes may do. Sessions without
+// scopes (a regular login) can do everything their role allows.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+    #[serde(rename = "questions:write")]
+    QuestionsWrite,
+    #[serde(rename = "answers:write")]
+    AnswersWrite,
+    #[serde(rename = "comments:write")]
+    CommentsWrite,
+    #[serde(rename = "account:read")]
+    AccountRead,
+    #[serde(rename = "account:write")]
+    AccountWrite,
+    #[serde(rename = "admin")]
+    Admin,
+}
+
+impl Scope {
+    pub const ALL: [Scope; 6] = [
+        Scope::QuestionsWrite,
+        Scope::AnswersWrite,
+        Scope::CommentsWrite,
+        Scope::AccountRead,
+        Scope::AccountWrite,
+        Scope::Admin,
+    ];
+
+    pub fn parse(scope: &str) -> Option<Scope> {
+        Scope::ALL.into_iter().find(|s| s.to_string() == scope)
+    }
+}
+
+impl Display for Scope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Scope::QuestionsWrite => write!(f, "questions:write"),
+            Scope::AnswersWrite => write!(f, "answers:write"),
+            Scope::CommentsWrite => write!(f, "comments:write"),
+            Scope::AccountRead => write!(f, "account:read"),
+            Scope::AccountWrite => write!(f, "account:write"),
+            Scope::Admin => write!(f, "admin"),
+        }
+    }
+}
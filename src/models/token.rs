@@ -0,0 +1,87 @@
+use std::net::IpAddr;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{account::AccountId, scope::Scope};
+
+// returned by /login and /token/refresh
+#[derive(Debug, Serialize)]
+pub struct TokenPair {
+    pub access_token: String,
+    pub refresh_token: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RefreshRequest {
+    pub refresh_token: String,
+}
+
+// asks for a token pair limited to `scopes`, e.g. read only for a dashboard.
+// It can't hold a scope the current session doesn't have.
+#[derive(Debug, Deserialize)]
+pub struct ScopedTokenRequest {
+    pub scopes: Vec<Scope>,
+}
+
+// a stored refresh token. All tokens rotated out of the same login share a family,
+// so presenting an already used token can revoke the whole chain.
+#[derive(Debug)]
+pub struct RefreshToken {
+    pub id: i32,
+    pub account_id: AccountId,
+    pub family_id: String,
+    pub expired: bool,
+    pub used: bool,
+    pub revoked: bool,
+    // set for families started from /token/scoped
+    pub scopes: Option<Vec<Scope>>,
+}
+
+// an access token revoked before its expiry, kept until it would have expired anyway.
+#[derive(Debug, Clone)]
+pub struct RevokedToken {
+    pub jti: String,
+    pub expires_on: DateTime<Utc>,
+}
+
+// where a login comes from, kept on its session record
+#[derive(Debug, Clone)]
+pub struct ClientInfo {
+    pub ip: Option<IpAddr>,
+    pub user_agent: Option<String>,
+}
+
+// how a session was logged into, kept on its session record
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoginMethod {
+    Password,
+    Oidc,
+    // a scoped token minted from another session
+    Scoped,
+}
+
+impl LoginMethod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LoginMethod::Password => "password",
+            LoginMethod::Oidc => "oidc",
+            LoginMethod::Scoped => "scoped",
+        }
+    }
+}
+
+// last_seen_on is only moved forward once this old, not on every request
+pub const LAST_SEEN_PRECISION_SECONDS: i64 = 60;
+
+// a device the account is logged in on, as listed by /account/sessions
+#[derive(Debug, Serialize)]
+pub struct ActiveSession {
+    pub id: i32,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub created_on: DateTime<Utc>,
+    pub last_seen_on: DateTime<Utc>,
+    // the session making the request
+    pub current: bool,
+}
@@ -0,0 +1,54 @@
+use serde::{Deserialize, Serialize};
+
+use super::account::AccountId;
+
+// the `purpose` claim of challenge tokens. Session tokens have none, which is
+// how `verify_token` refuses to accept a challenge as a session.
+pub const CHALLENGE_PURPOSE: &str = "2fa_challenge";
+
+// returned when enrolling, to be scanned or typed in an authenticator app
+#[derive(Debug, Serialize)]
+pub struct TwoFactorEnrollment {
+    pub secret: String,
+    pub otpauth_uri: String,
+}
+
+// a totp code, or a recovery code where the account already has 2FA enabled
+#[derive(Debug, Deserialize)]
+pub struct TwoFactorCode {
+    pub code: String,
+}
+
+// shown once, when two-factor authentication is confirmed
+#[derive(Debug, Serialize)]
+pub struct RecoveryCodes {
+    pub recovery_codes: Vec<String>,
+}
+
+// what /login returns instead of a token pair when the account has 2FA enabled
+#[derive(Debug, Serialize)]
+pub struct TwoFactorChallenge {
+    pub two_factor_required: bool,
+    pub challenge_token: String,
+}
+
+// second login step, `code` is either a totp code or a recovery code
+#[derive(Debug, Deserialize)]
+pub struct ChallengeResponse {
+    pub challenge_token: String,
+    pub code: String,
+}
+
+#[derive(Debug)]
+pub struct TotpState {
+    pub secret: Option<String>,
+    pub enabled: bool,
+}
+
+// claims of the challenge token handed out by /login
+#[derive(Debug, Deserialize)]
+pub struct LoginChallenge {
+    pub jti: String,
+    pub account_id: AccountId,
+    pub purpose: String,
+}
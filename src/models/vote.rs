@@ -0,0 +1,30 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Vote {
+    Up,
+    Down,
+}
+
+impl Vote {
+    // what the vote adds to the score
+    pub fn value(self) -> i16 {
+        match self {
+            Vote::Up => 1,
+            Vote::Down => -1,
+        }
+    }
+}
+
+// body of POST /question/{id}/vote and /answer/{id}/vote: {"vote": "up"}
+#[derive(Debug, Deserialize)]
+pub struct VoteDTO {
+    pub vote: Vote,
+}
+
+// returned after voting, the target's score with the change applied
+#[derive(Debug, Serialize)]
+pub struct Score {
+    pub score: i32,
+}
@@ -1,26 +1,167 @@
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 
 use crate::{
+    config::{AccountDeletion, ReputationPoints},
     custom_errors::repository::Error,
     models::{
-        account::{Account, AccountId},
-        answer::{Answer, AnswerDTO, AnswerId},
-        question::{Question, QuestionDTO, QuestionId},
+        account::{Account, AccountId, AccountView, NewAccount, PasswordHash, Role},
+        api_key::{ApiKey, ApiKeyId, ApiKeyOwner},
+        answer::{Answer, AnswerDTO, AnswerId, AnswerSort},
+        comment::{Comment, CommentDTO, CommentId},
+        oidc::{ExternalIdentity, OidcLogin},
+        post::PostId,
+        profile::{Profile, ProfileUpdate, PublicProfile},
+        question::{Question, QuestionDTO, QuestionDetail, QuestionId},
+        token::{ActiveSession, ClientInfo, LoginMethod, RefreshToken, RevokedToken},
+        two_factor::TotpState,
+        vote::Vote,
     },
 };
 
 #[async_trait]
 pub trait DatabaseRepository {
-    async fn add_account(&self, account: Account) -> Result<bool, Error>;
+    async fn add_account(
+        &self,
+        account: NewAccount,
+        verification_token_hash: &str,
+        ttl_seconds: i64,
+    ) -> Result<AccountId, Error>;
     async fn get_account(&self, email: &str) -> Result<Account, Error>;
+    async fn get_account_by_id(&self, account_id: AccountId) -> Result<Account, Error>;
+    async fn get_accounts(&self, limit: Option<i16>, offset: i16) -> Result<Vec<AccountView>, Error>;
+    async fn update_account_role(&self, account_id: AccountId, role: Role) -> Result<AccountView, Error>;
+    async fn get_profile(&self, account_id: AccountId) -> Result<Profile, Error>;
+    async fn update_profile(&self, account_id: AccountId, profile: ProfileUpdate) -> Result<Profile, Error>;
+    async fn get_public_profile(&self, account_id: AccountId) -> Result<PublicProfile, Error>;
+
+    async fn update_account_password(&self, account_id: AccountId, password: &PasswordHash) -> Result<bool, Error>;
+    async fn update_password_hash(
+        &self,
+        account_id: AccountId,
+        current: &PasswordHash,
+        rehashed: &PasswordHash,
+    ) -> Result<bool, Error>;
+    async fn get_account_questions(&self, account_id: AccountId) -> Result<Vec<Question>, Error>;
+    async fn get_account_answers(&self, account_id: AccountId) -> Result<Vec<Answer>, Error>;
+    async fn get_account_comments(&self, account_id: AccountId) -> Result<Vec<Comment>, Error>;
+    async fn delete_account(
+        &self,
+        account_id: AccountId,
+        mode: AccountDeletion,
+        points: ReputationPoints,
+    ) -> Result<bool, Error>;
+
+    async fn add_password_reset_token(
+        &self,
+        account_id: AccountId,
+        token_hash: &str,
+        ttl_seconds: i64,
+    ) -> Result<bool, Error>;
+    async fn use_password_reset_token(&self, token_hash: &str) -> Result<AccountId, Error>;
+
+    async fn add_email_verification_token(
+        &self,
+        account_id: AccountId,
+        token_hash: &str,
+        ttl_seconds: i64,
+    ) -> Result<bool, Error>;
+    async fn verify_email(&self, token_hash: &str) -> Result<AccountId, Error>;
+    async fn is_account_verified(&self, account_id: AccountId) -> Result<bool, Error>;
+
+    async fn get_totp(&self, account_id: AccountId) -> Result<TotpState, Error>;
+    async fn set_totp_secret(&self, account_id: AccountId, secret: &str) -> Result<bool, Error>;
+    async fn enable_totp(&self, account_id: AccountId, recovery_code_hashes: Vec<String>) -> Result<bool, Error>;
+    async fn disable_totp(&self, account_id: AccountId) -> Result<bool, Error>;
+    async fn use_totp_step(&self, account_id: AccountId, step: i64) -> Result<bool, Error>;
+    async fn use_recovery_code(&self, account_id: AccountId, code_hash: &str) -> Result<bool, Error>;
+    async fn add_login_challenge(&self, jti: &str, account_id: AccountId, ttl_seconds: i64) -> Result<bool, Error>;
+    async fn use_login_challenge(&self, jti: &str) -> Result<AccountId, Error>;
+
+    async fn add_oidc_login(
+        &self,
+        state_hash: &str,
+        login: &OidcLogin,
+        ttl_seconds: i64,
+    ) -> Result<bool, Error>;
+    async fn use_oidc_login(&self, state_hash: &str) -> Result<OidcLogin, Error>;
+    async fn get_account_by_identity(&self, issuer: &str, subject: &str) -> Result<Account, Error>;
+    async fn add_account_identity(
+        &self,
+        identity: &ExternalIdentity,
+        email: &str,
+        password: &PasswordHash,
+    ) -> Result<Option<AccountId>, Error>;
+
+    async fn add_refresh_token(
+        &self,
+        account_id: AccountId,
+        family_id: &str,
+        token_hash: &str,
+        ttl_seconds: i64,
+        scopes: Option<Vec<String>>,
+    ) -> Result<bool, Error>;
+    async fn get_refresh_token(&self, token_hash: &str) -> Result<RefreshToken, Error>;
+    async fn rotate_refresh_token(
+        &self,
+        current: &RefreshToken,
+        token_hash: &str,
+        ttl_seconds: i64,
+    ) -> Result<bool, Error>;
+    async fn revoke_refresh_token_family(&self, family_id: &str) -> Result<bool, Error>;
+    async fn revoke_account_refresh_tokens(
+        &self,
+        account_id: AccountId,
+        keep_family_id: Option<&str>,
+    ) -> Result<bool, Error>;
+
+    async fn add_session(
+        &self,
+        account_id: AccountId,
+        family_id: &str,
+        client: &ClientInfo,
+        method: LoginMethod,
+    ) -> Result<bool, Error>;
+    async fn is_recent_login(
+        &self,
+        family_id: &str,
+        method: LoginMethod,
+        max_age_seconds: i64,
+    ) -> Result<bool, Error>;
+    async fn touch_session(&self, family_id: &str) -> Result<bool, Error>;
+    async fn get_sessions(
+        &self,
+        account_id: AccountId,
+        current_family_id: &str,
+    ) -> Result<Vec<ActiveSession>, Error>;
+    async fn delete_session(&self, account_id: AccountId, session_id: i32) -> Result<bool, Error>;
+
+    async fn add_api_key(
+        &self,
+        account_id: AccountId,
+        name: &str,
+        prefix: &str,
+        key_hash: &str,
+        scopes: Vec<String>,
+        ttl_seconds: Option<i64>,
+    ) -> Result<ApiKey, Error>;
+    async fn get_api_keys(&self, account_id: AccountId) -> Result<Vec<ApiKey>, Error>;
+    async fn revoke_api_key(&self, account_id: AccountId, api_key_id: ApiKeyId) -> Result<bool, Error>;
+    async fn use_api_key(&self, key_hash: &str) -> Result<ApiKeyOwner, Error>;
+
+    async fn revoke_token(&self, jti: &str, expires_on: DateTime<Utc>) -> Result<bool, Error>;
+    async fn get_revoked_tokens(&self) -> Result<Vec<RevokedToken>, Error>;
+    async fn purge_revoked_tokens(&self) -> Result<bool, Error>;
 
     async fn create_question(
         &self,
         question: QuestionDTO,
         account_id: AccountId,
+        points: ReputationPoints,
     ) -> Result<Question, Error>;
 
     async fn get_question(&self, question_id: QuestionId) -> Result<Question, Error>;
+    async fn get_question_with_answers(&self, question_id: QuestionId) -> Result<QuestionDetail, Error>;
     async fn get_questions(&self, limit: Option<i16>, offset: i16) -> Result<Vec<Question>, Error>;
 
     async fn update_question(
@@ -30,9 +171,47 @@ pub trait DatabaseRepository {
     ) -> Result<Question, Error>;
 
     async fn delete_question(&self, question_id: QuestionId) -> Result<bool, Error>;
+    async fn accept_answer(
+        &self,
+        question_id: QuestionId,
+        answer_id: AnswerId,
+        points: ReputationPoints,
+    ) -> Result<bool, Error>;
+    async fn unaccept_answer(&self, question_id: QuestionId) -> Result<bool, Error>;
     async fn is_question_owner(&self, question_id: QuestionId, account_id: AccountId) -> Result<bool, Error>;
 
+    async fn get_answer(&self, answer_id: AnswerId) -> Result<Answer, Error>;
+    async fn get_answers(
+        &self,
+        question_id: QuestionId,
+        sort: AnswerSort,
+        limit: Option<i16>,
+        offset: i16,
+    ) -> Result<Vec<Answer>, Error>;
     async fn is_answer_owner(&self, answer_id: AnswerId, account_id: AccountId) -> Result<bool, Error>;
-    async fn create_answer(&self, answer: AnswerDTO, account_id: AccountId) -> Result<Answer, Error>;
+    async fn create_answer(
+        &self,
+        answer: AnswerDTO,
+        account_id: AccountId,
+        points: ReputationPoints,
+    ) -> Result<Answer, Error>;
     async fn update_answer(&self, answer: AnswerDTO, answer_id: AnswerId) -> Result<Answer, Error>;
+    async fn delete_answer(&self, answer_id: AnswerId) -> Result<bool, Error>;
+
+    // None takes the account's vote back. Returns the target's new score.
+    async fn cast_vote(
+        &self,
+        account_id: AccountId,
+        target: PostId,
+        vote: Option<Vote>,
+        points: ReputationPoints,
+    ) -> Result<i32, Error>;
+
+    async fn create_comment(&self, comment: CommentDTO, account_id: AccountId, post: PostId) -> Result<Comment, Error>;
+    async fn update_comment(&self, comment: CommentDTO, comment_id: CommentId) -> Result<Comment, Error>;
+    async fn delete_comment(&self, comment_id: CommentId) -> Result<bool, Error>;
+    async fn is_comment_owner(&self, comment_id: CommentId, account_id: AccountId) -> Result<bool, Error>;
+
+    async fn get_reputation(&self, account_id: AccountId) -> Result<i32, Error>;
+    async fn replay_reputation(&self, points: ReputationPoints) -> Result<bool, Error>;
 }
\ No newline at end of file
@@ -1,16 +1,35 @@
 use sqlx::{
     postgres::{PgPoolOptions, PgRow},
-    PgPool, Row,
+    PgPool, Postgres, Row, Transaction,
 };
 
+use std::collections::HashMap;
+
 use async_trait::async_trait;
+use chrono::{DateTime, NaiveDateTime, Utc};
 
 use crate::{
+    config::{AccountDeletion, ReputationPoints},
     custom_errors::repository::Error,
     models::{
-        account::{Account, AccountId},
-        answer::{Answer, AnswerDTO, AnswerId},
-        question::{QuestionDTO, Question, QuestionId},
+        account::{
+            Account, AccountId, AccountView, NewAccount, PasswordHash, Role, DELETED_ACCOUNT_ID,
+        },
+        api_key::{ApiKey, ApiKeyId, ApiKeyOwner},
+        answer::{Answer, AnswerDTO, AnswerId, AnswerSort},
+        comment::{Comment, CommentDTO, CommentId},
+        oidc::{ExternalIdentity, OidcLogin},
+        post::PostId,
+        profile::{Author, Profile, ProfileUpdate, PublicProfile},
+        reputation::ReputationEvent,
+        question::{QuestionDTO, Question, QuestionDetail, QuestionId},
+        scope::Scope,
+        token::{
+            ActiveSession, ClientInfo, LoginMethod, RefreshToken, RevokedToken,
+            LAST_SEEN_PRECISION_SECONDS,
+        },
+        two_factor::TotpState,
+        vote::Vote,
     },
 };
 
@@ -34,34 +53,1338 @@ impl Repository {
 
 #[async_trait]
 impl DatabaseRepository for Repository {
-    async fn add_account(&self, account: Account) -> Result<bool, Error> {
-        match sqlx::query(
+    // the account comes with the token mailed to verify its email, it is
+    // never left without one.
+    async fn add_account(
+        &self,
+        account: NewAccount,
+        verification_token_hash: &str,
+        ttl_seconds: i64,
+    ) -> Result<AccountId, Error> {
+        let mut tx = self
+            .db_pool
+            .begin()
+            .await
+            .map_err(Error::DatabaseQueryError)?;
+
+        let account_id = sqlx::query(
             "INSERT INTO accounts (email, password) 
             VALUES ($1, $2)  returning id, email",
         )
         .bind(account.email)
-        .bind(account.password)
-        .execute(&self.db_pool)
+        .bind(account.password.as_str())
+        .map(|row: PgRow| AccountId(row.get("id")))
+        .fetch_one(&mut tx)
+        .await
+        .map_err(Error::DatabaseQueryError)?;
+
+        sqlx::query(
+            "INSERT INTO public.email_verification_tokens
+                        (account_id, token_hash, expires_on)
+                 VALUES ($1, $2, NOW() + $3 * INTERVAL '1 second')",
+        )
+        .bind(account_id.0)
+        .bind(verification_token_hash)
+        .bind(ttl_seconds as f64)
+        .execute(&mut tx)
         .await
+        .map_err(Error::DatabaseQueryError)?;
+
+        tx.commit().await.map_err(Error::DatabaseQueryError)?;
+
+        Ok(account_id)
+    }
+
+    async fn get_account(&self, email: &str) -> Result<Account, Error> {
+        match sqlx::query("SELECT * FROM public.accounts where email = $1;")
+            .bind(email)
+            .map(|row: PgRow| Account {
+                id: AccountId(row.get("id")),
+                email: row.get("email"),
+                password: PasswordHash::new(row.get("password")),
+                role: Role::from(row.get::<&str, _>("role")),
+                two_factor_enabled: row
+                    .get::<Option<NaiveDateTime>, _>("totp_enabled_at")
+                    .is_some(),
+                has_password: row.get("password_set"),
+            })
+            .fetch_one(&self.db_pool)
+            .await
         {
-            Ok(_) => Ok(true),
+            Ok(account) => Ok(account),
+            Err(error) => Err(Error::DatabaseQueryError(error)),
+        }
+    }
+
+    async fn get_account_by_id(&self, account_id: AccountId) -> Result<Account, Error> {
+        match sqlx::query("SELECT * FROM public.accounts where id = $1;")
+            .bind(account_id.0)
+            .map(|row: PgRow| Account {
+                id: AccountId(row.get("id")),
+                email: row.get("email"),
+                password: PasswordHash::new(row.get("password")),
+                role: Role::from(row.get::<&str, _>("role")),
+                two_factor_enabled: row
+                    .get::<Option<NaiveDateTime>, _>("totp_enabled_at")
+                    .is_some(),
+                has_password: row.get("password_set"),
+            })
+            .fetch_one(&self.db_pool)
+            .await
+        {
+            Ok(account) => Ok(account),
             Err(error) => Err(Error::DatabaseQueryError(error)),
         }
     }
 
-    async fn get_account(&self, email: &str) -> Result<Account, Error> {
-        match sqlx::query("SELECT * FROM public.accounts where email = $1;")
-            .bind(email)
-            .map(|row: PgRow| Account {
-                id: Some(AccountId(row.get("id"))),
-                email: row.get("email"),
-                password: row.get("password"),
-            })
-            .fetch_one(&self.db_pool)
-            .await
-        {
-            Ok(account) => Ok(account),
-            Err(error) => Err(Error::DatabaseQueryError(error)),
+    async fn get_accounts(&self, limit: Option<i16>, offset: i16) -> Result<Vec<AccountView>, Error> {
+        let result = sqlx::query(
+            "SELECT id, email, role, reputation
+                   FROM public.accounts
+                  ORDER BY id
+                  LIMIT $1
+                 OFFSET $2",
+        )
+        .bind(limit)
+        .bind(offset)
+        .map(|row: PgRow| AccountView {
+            id: AccountId(row.get("id")),
+            email: row.get("email"),
+            role: Role::from(row.get::<&str, _>("role")),
+            reputation: row.get("reputation"),
+        })
+        .fetch_all(&self.db_pool)
+        .await;
+
+        match result {
+            Ok(accounts) => Ok(accounts),
+            Err(e) => Err(Error::DatabaseQueryError(e)),
+        }
+    }
+
+    async fn update_account_role(&self, account_id: AccountId, role: Role) -> Result<AccountView, Error> {
+        let result = sqlx::query(
+            "UPDATE public.accounts SET role = $1
+              WHERE id = $2
+              RETURNING id, email, role, reputation",
+        )
+        .bind(role.to_string())
+        .bind(account_id.0)
+        .map(|row: PgRow| AccountView {
+            id: AccountId(row.get("id")),
+            email: row.get("email"),
+            role: Role::from(row.get::<&str, _>("role")),
+            reputation: row.get("reputation"),
+        })
+        .fetch_one(&self.db_pool)
+        .await;
+
+        match result {
+            Ok(account) => Ok(account),
+            Err(e) => Err(Error::DatabaseQueryError(e)),
+        }
+    }
+
+    async fn get_profile(&self, account_id: AccountId) -> Result<Profile, Error> {
+        let result = sqlx::query(
+            "SELECT id, email, display_name, bio, avatar_url, reputation, created_on
+               FROM public.accounts
+              WHERE id = $1",
+        )
+        .bind(account_id.0)
+        .map(|row: PgRow| Profile {
+            id: AccountId(row.get("id")),
+            email: row.get("email"),
+            display_name: row.get("display_name"),
+            bio: row.get("bio"),
+            avatar_url: row.get("avatar_url"),
+            reputation: row.get("reputation"),
+            created_on: row.get("created_on"),
+        })
+        .fetch_one(&self.db_pool)
+        .await;
+
+        match result {
+            Ok(profile) => Ok(profile),
+            Err(e) => Err(Error::DatabaseQueryError(e)),
+        }
+    }
+
+    async fn update_profile(&self, account_id: AccountId, profile: ProfileUpdate) -> Result<Profile, Error> {
+        // blank values clear the field
+        let result = sqlx::query(
+            "UPDATE public.accounts
+                SET display_name = NULLIF(TRIM($1), ''),
+                    bio = NULLIF(TRIM($2), ''),
+                    avatar_url = NULLIF(TRIM($3), '')
+              WHERE id = $4
+              RETURNING id, email, display_name, bio, avatar_url, reputation, created_on",
+        )
+        .bind(profile.display_name)
+        .bind(profile.bio)
+        .bind(profile.avatar_url)
+        .bind(account_id.0)
+        .map(|row: PgRow| Profile {
+            id: AccountId(row.get("id")),
+            email: row.get("email"),
+            display_name: row.get("display_name"),
+            bio: row.get("bio"),
+            avatar_url: row.get("avatar_url"),
+            reputation: row.get("reputation"),
+            created_on: row.get("created_on"),
+        })
+        .fetch_one(&self.db_pool)
+        .await;
+
+        match result {
+            Ok(profile) => Ok(profile),
+            Err(e) => Err(Error::DatabaseQueryError(e)),
+        }
+    }
+
+    async fn get_public_profile(&self, account_id: AccountId) -> Result<PublicProfile, Error> {
+        let result = sqlx::query(
+            "SELECT id, display_name, bio, avatar_url, reputation, created_on
+               FROM public.accounts
+              WHERE id = $1",
+        )
+        .bind(account_id.0)
+        .map(|row: PgRow| PublicProfile {
+            id: AccountId(row.get("id")),
+            display_name: row.get("display_name"),
+            bio: row.get("bio"),
+            avatar_url: row.get("avatar_url"),
+            reputation: row.get("reputation"),
+            created_on: row.get("created_on"),
+        })
+        .fetch_one(&self.db_pool)
+        .await;
+
+        match result {
+            Ok(profile) => Ok(profile),
+            Err(e) => Err(Error::DatabaseQueryError(e)),
+        }
+    }
+
+    async fn update_account_password(&self, account_id: AccountId, password: &PasswordHash) -> Result<bool, Error> {
+        let result = sqlx::query("UPDATE public.accounts SET password = $1, password_set = TRUE WHERE id = $2")
+            .bind(password.as_str())
+            .bind(account_id.0)
+            .execute(&self.db_pool)
+            .await;
+
+        match result {
+            Ok(done) => Ok(done.rows_affected() == 1),
+            Err(e) => Err(Error::DatabaseQueryError(e)),
+        }
+    }
+
+    // same password, stronger hash. Skipped if the password was changed
+    // since `current` was read.
+    async fn update_password_hash(
+        &self,
+        account_id: AccountId,
+        current: &PasswordHash,
+        rehashed: &PasswordHash,
+    ) -> Result<bool, Error> {
+        let result = sqlx::query(
+            "UPDATE public.accounts SET password = $1
+              WHERE id = $2 AND password = $3",
+        )
+        .bind(rehashed.as_str())
+        .bind(account_id.0)
+        .bind(current.as_str())
+        .execute(&self.db_pool)
+        .await;
+
+        match result {
+            Ok(done) => Ok(done.rows_affected() == 1),
+            Err(e) => Err(Error::DatabaseQueryError(e)),
+        }
+    }
+
+    async fn get_account_questions(&self, account_id: AccountId) -> Result<Vec<Question>, Error> {
+        let result = sqlx::query(
+            "SELECT q.id, q.title, q.content, q.tags, q.score, q.account_id, q.created_on,
+                    a.display_name, a.avatar_url
+               FROM public.questions q
+               LEFT JOIN public.accounts a ON a.id = q.account_id
+              WHERE q.account_id = $1
+              ORDER BY q.created_on",
+        )
+        .bind(account_id.0)
+        .map(question_from_row)
+        .fetch_all(&self.db_pool)
+        .await;
+
+        match result {
+            Ok(questions) => Ok(questions),
+            Err(e) => Err(Error::DatabaseQueryError(e)),
+        }
+    }
+
+    async fn get_account_answers(&self, account_id: AccountId) -> Result<Vec<Answer>, Error> {
+        let result = sqlx::query(
+            "SELECT n.id, n.content, n.score, n.account_id, n.question_id,
+                    COALESCE(q.accepted_answer_id = n.id, false) AS is_accepted,
+                    a.display_name, a.avatar_url
+               FROM public.answers n
+               LEFT JOIN public.questions q ON q.id = n.question_id
+               LEFT JOIN public.accounts a ON a.id = n.account_id
+              WHERE n.account_id = $1
+              ORDER BY n.created_on",
+        )
+        .bind(account_id.0)
+        .map(answer_from_row)
+        .fetch_all(&self.db_pool)
+        .await;
+
+        match result {
+            Ok(answers) => Ok(answers),
+            Err(e) => Err(Error::DatabaseQueryError(e)),
+        }
+    }
+
+    async fn get_account_comments(&self, account_id: AccountId) -> Result<Vec<Comment>, Error> {
+        let result = sqlx::query(
+            "SELECT c.id, c.content, c.account_id, c.question_id, c.answer_id,
+                    c.created_on, c.updated_on, a.display_name, a.avatar_url
+               FROM public.comments c
+               LEFT JOIN public.accounts a ON a.id = c.account_id
+              WHERE c.account_id = $1
+              ORDER BY c.created_on",
+        )
+        .bind(account_id.0)
+        .map(comment_from_row)
+        .fetch_all(&self.db_pool)
+        .await;
+
+        match result {
+            Ok(comments) => Ok(comments),
+            Err(e) => Err(Error::DatabaseQueryError(e)),
+        }
+    }
+
+    // tokens, sessions, api keys and the like go with the account (on delete
+    // cascade). Questions and answers have no foreign key and are handled here.
+    async fn delete_account(
+        &self,
+        account_id: AccountId,
+        mode: AccountDeletion,
+        points: ReputationPoints,
+    ) -> Result<bool, Error> {
+        let mut tx = self
+            .db_pool
+            .begin()
+            .await
+            .map_err(Error::DatabaseQueryError)?;
+
+        // the authors lose what the account's votes earned them, as if each
+        // vote was taken back. The ledger doesn't say who voted, so this
+        // can't go through `reverse_reputation`.
+        let votes = sqlx::query(
+            "SELECT v.value,
+                    COALESCE(q.account_id, a.account_id) AS author,
+                    COALESCE(v.question_id, a.question_id) AS question_id,
+                    v.answer_id
+               FROM public.votes v
+               LEFT JOIN public.questions q ON q.id = v.question_id
+               LEFT JOIN public.answers a ON a.id = v.answer_id
+              WHERE v.account_id = $1",
+        )
+        .bind(account_id.0)
+        .map(|row: PgRow| {
+            (
+                row.get::<i16, _>("value"),
+                row.get::<Option<i32>, _>("author"),
+                row.get::<i32, _>("question_id"),
+                row.get::<Option<i32>, _>("answer_id"),
+            )
+        })
+        .fetch_all(&mut tx)
+        .await
+        .map_err(Error::DatabaseQueryError)?;
+
+        for (value, author, question_id, answer_id) in votes {
+            if let Some(author) = author {
+                record_reputation(&mut tx, author, vote_event(value), -1, &points, question_id, answer_id).await?;
+            }
+        }
+
+        // the account's votes go with it (on delete cascade), so they are
+        // taken off the scores they counted towards first.
+        for statement in [
+            "UPDATE public.questions q SET score = q.score - v.total
+               FROM (SELECT question_id, SUM(value) AS total
+                       FROM public.votes
+                      WHERE account_id = $1 AND question_id IS NOT NULL
+                      GROUP BY question_id) v
+              WHERE q.id = v.question_id",
+            "UPDATE public.answers n SET score = n.score - v.total
+               FROM (SELECT answer_id, SUM(value) AS total
+                       FROM public.votes
+                      WHERE account_id = $1 AND answer_id IS NOT NULL
+                      GROUP BY answer_id) v
+              WHERE n.id = v.answer_id",
+        ] {
+            sqlx::query(statement)
+                .bind(account_id.0)
+                .execute(&mut tx)
+                .await
+                .map_err(Error::DatabaseQueryError)?;
+        }
+
+        // the account's own ledger goes with it, but other accounts' answers
+        // on its questions are deleted too and take their points along.
+        if mode == AccountDeletion::HardDelete {
+            reverse_reputation(
+                &mut tx,
+                "question_id IN (SELECT id FROM public.questions WHERE account_id = $1)
+                    OR answer_id IN (SELECT id FROM public.answers WHERE account_id = $1)",
+                account_id.0,
+            )
+            .await?;
+        }
+
+        let statements: &[&str] = match mode {
+            AccountDeletion::Anonymize => &[
+                "UPDATE public.comments SET account_id = $2 WHERE account_id = $1",
+                "UPDATE public.answers SET account_id = $2 WHERE account_id = $1",
+                "UPDATE public.questions SET account_id = $2 WHERE account_id = $1",
+            ],
+            // answers from other accounts go with the questions they belong to
+            AccountDeletion::HardDelete => &[
+                "DELETE FROM public.comments WHERE account_id = $1",
+                "DELETE FROM public.answers
+                  WHERE account_id = $1
+                     OR question_id IN (SELECT id FROM public.questions WHERE account_id = $1)",
+                "DELETE FROM public.questions WHERE account_id = $1",
+            ],
+        };
+
+        for statement in statements {
+            sqlx::query(statement)
+                .bind(account_id.0)
+                .bind(DELETED_ACCOUNT_ID)
+                .execute(&mut tx)
+                .await
+                .map_err(Error::DatabaseQueryError)?;
+        }
+
+        let deleted = sqlx::query("DELETE FROM public.accounts WHERE id = $1")
+            .bind(account_id.0)
+            .execute(&mut tx)
+            .await
+            .map_err(Error::DatabaseQueryError)?;
+
+        tx.commit().await.map_err(Error::DatabaseQueryError)?;
+
+        Ok(deleted.rows_affected() == 1)
+    }
+
+    async fn add_password_reset_token(
+        &self,
+        account_id: AccountId,
+        token_hash: &str,
+        ttl_seconds: i64,
+    ) -> Result<bool, Error> {
+        let result = sqlx::query(
+            "INSERT INTO public.password_reset_tokens
+                        (account_id, token_hash, expires_on)
+                 VALUES ($1, $2, NOW() + $3 * INTERVAL '1 second')",
+        )
+        .bind(account_id.0)
+        .bind(token_hash)
+        .bind(ttl_seconds as f64)
+        .execute(&self.db_pool)
+        .await;
+
+        match result {
+            Ok(_) => Ok(true),
+            Err(e) => Err(Error::DatabaseQueryError(e)),
+        }
+    }
+
+    async fn use_password_reset_token(&self, token_hash: &str) -> Result<AccountId, Error> {
+        // marking it used in the same statement makes the token single-use
+        // even when two confirmations race each other.
+        let result = sqlx::query(
+            "UPDATE public.password_reset_tokens SET used_on = NOW()
+              WHERE token_hash = $1 AND used_on IS NULL AND expires_on > NOW()
+              RETURNING account_id",
+        )
+        .bind(token_hash)
+        .map(|row: PgRow| AccountId(row.get("account_id")))
+        .fetch_one(&self.db_pool)
+        .await;
+
+        match result {
+            Ok(account_id) => Ok(account_id),
+            Err(e) => Err(Error::DatabaseQueryError(e)),
+        }
+    }
+
+    async fn add_email_verification_token(
+        &self,
+        account_id: AccountId,
+        token_hash: &str,
+        ttl_seconds: i64,
+    ) -> Result<bool, Error> {
+        let result = sqlx::query(
+            "INSERT INTO public.email_verification_tokens
+                        (account_id, token_hash, expires_on)
+                 VALUES ($1, $2, NOW() + $3 * INTERVAL '1 second')",
+        )
+        .bind(account_id.0)
+        .bind(token_hash)
+        .bind(ttl_seconds as f64)
+        .execute(&self.db_pool)
+        .await;
+
+        match result {
+            Ok(_) => Ok(true),
+            Err(e) => Err(Error::DatabaseQueryError(e)),
+        }
+    }
+
+    async fn verify_email(&self, token_hash: &str) -> Result<AccountId, Error> {
+        let result = sqlx::query(
+            "WITH used AS (
+                 UPDATE public.email_verification_tokens SET used_on = NOW()
+                  WHERE token_hash = $1 AND used_on IS NULL AND expires_on > NOW()
+                  RETURNING account_id
+             )
+             UPDATE public.accounts SET verified_at = COALESCE(verified_at, NOW())
+               FROM used
+              WHERE accounts.id = used.account_id
+              RETURNING accounts.id",
+        )
+        .bind(token_hash)
+        .map(|row: PgRow| AccountId(row.get("id")))
+        .fetch_one(&self.db_pool)
+        .await;
+
+        match result {
+            Ok(account_id) => Ok(account_id),
+            Err(e) => Err(Error::DatabaseQueryError(e)),
+        }
+    }
+
+    async fn is_account_verified(&self, account_id: AccountId) -> Result<bool, Error> {
+        let result = sqlx::query(
+            "SELECT id FROM public.accounts WHERE id = $1 AND verified_at IS NOT NULL",
+        )
+        .bind(account_id.0)
+        .fetch_optional(&self.db_pool)
+        .await;
+
+        match result {
+            Ok(account) => Ok(account.is_some()),
+            Err(e) => Err(Error::DatabaseQueryError(e)),
+        }
+    }
+
+    async fn get_totp(&self, account_id: AccountId) -> Result<TotpState, Error> {
+        let result = sqlx::query(
+            "SELECT totp_secret, totp_enabled_at IS NOT NULL AS enabled
+               FROM public.accounts
+              WHERE id = $1",
+        )
+        .bind(account_id.0)
+        .map(|row: PgRow| TotpState {
+            secret: row.get("totp_secret"),
+            enabled: row.get("enabled"),
+        })
+        .fetch_one(&self.db_pool)
+        .await;
+
+        match result {
+            Ok(state) => Ok(state),
+            Err(e) => Err(Error::DatabaseQueryError(e)),
+        }
+    }
+
+    async fn set_totp_secret(&self, account_id: AccountId, secret: &str) -> Result<bool, Error> {
+        // a confirmed secret is only replaced by disabling 2FA first
+        let result = sqlx::query(
+            "UPDATE public.accounts SET totp_secret = $1, totp_last_step = NULL
+              WHERE id = $2 AND totp_enabled_at IS NULL",
+        )
+        .bind(secret)
+        .bind(account_id.0)
+        .execute(&self.db_pool)
+        .await;
+
+        match result {
+            Ok(done) => Ok(done.rows_affected() == 1),
+            Err(e) => Err(Error::DatabaseQueryError(e)),
+        }
+    }
+
+    async fn enable_totp(&self, account_id: AccountId, recovery_code_hashes: Vec<String>) -> Result<bool, Error> {
+        let mut tx = self
+            .db_pool
+            .begin()
+            .await
+            .map_err(Error::DatabaseQueryError)?;
+
+        let enabled = sqlx::query(
+            "UPDATE public.accounts SET totp_enabled_at = NOW()
+              WHERE id = $1 AND totp_secret IS NOT NULL AND totp_enabled_at IS NULL",
+        )
+        .bind(account_id.0)
+        .execute(&mut tx)
+        .await
+        .map_err(Error::DatabaseQueryError)?;
+
+        if enabled.rows_affected() != 1 {
+            tx.rollback().await.map_err(Error::DatabaseQueryError)?;
+            return Ok(false);
+        }
+
+        sqlx::query("DELETE FROM public.recovery_codes WHERE account_id = $1")
+            .bind(account_id.0)
+            .execute(&mut tx)
+            .await
+            .map_err(Error::DatabaseQueryError)?;
+
+        sqlx::query(
+            "INSERT INTO public.recovery_codes (account_id, code_hash)
+             SELECT $1, code_hash FROM UNNEST($2::varchar[]) AS code_hash",
+        )
+        .bind(account_id.0)
+        .bind(recovery_code_hashes)
+        .execute(&mut tx)
+        .await
+        .map_err(Error::DatabaseQueryError)?;
+
+        tx.commit().await.map_err(Error::DatabaseQueryError)?;
+
+        Ok(true)
+    }
+
+    async fn disable_totp(&self, account_id: AccountId) -> Result<bool, Error> {
+        let mut tx = self
+            .db_pool
+            .begin()
+            .await
+            .map_err(Error::DatabaseQueryError)?;
+
+        sqlx::query(
+            "UPDATE public.accounts
+                SET totp_secret = NULL, totp_enabled_at = NULL, totp_last_step = NULL
+              WHERE id = $1",
+        )
+        .bind(account_id.0)
+        .execute(&mut tx)
+        .await
+        .map_err(Error::DatabaseQueryError)?;
+
+        sqlx::query("DELETE FROM public.recovery_codes WHERE account_id = $1")
+            .bind(account_id.0)
+            .execute(&mut tx)
+            .await
+            .map_err(Error::DatabaseQueryError)?;
+
+        tx.commit().await.map_err(Error::DatabaseQueryError)?;
+
+        Ok(true)
+    }
+
+    async fn use_totp_step(&self, account_id: AccountId, step: i64) -> Result<bool, Error> {
+        // a code is accepted once: only a step later than the last one used moves it forward
+        let result = sqlx::query(
+            "UPDATE public.accounts SET totp_last_step = $1
+              WHERE id = $2 AND (totp_last_step IS NULL OR totp_last_step < $1)",
+        )
+        .bind(step)
+        .bind(account_id.0)
+        .execute(&self.db_pool)
+        .await;
+
+        match result {
+            Ok(done) => Ok(done.rows_affected() == 1),
+            Err(e) => Err(Error::DatabaseQueryError(e)),
+        }
+    }
+
+    async fn use_recovery_code(&self, account_id: AccountId, code_hash: &str) -> Result<bool, Error> {
+        let result = sqlx::query(
+            "UPDATE public.recovery_codes SET used_on = NOW()
+              WHERE account_id = $1 AND code_hash = $2 AND used_on IS NULL",
+        )
+        .bind(account_id.0)
+        .bind(code_hash)
+        .execute(&self.db_pool)
+        .await;
+
+        match result {
+            Ok(done) => Ok(done.rows_affected() == 1),
+            Err(e) => Err(Error::DatabaseQueryError(e)),
+        }
+    }
+
+    async fn add_login_challenge(&self, jti: &str, account_id: AccountId, ttl_seconds: i64) -> Result<bool, Error> {
+        // challenges abandoned after the password step are never used
+        let purged = sqlx::query("DELETE FROM public.login_challenges WHERE expires_on <= NOW()")
+            .execute(&self.db_pool)
+            .await;
+
+        if let Err(e) = purged {
+            return Err(Error::DatabaseQueryError(e));
+        }
+
+        let result = sqlx::query(
+            "INSERT INTO public.login_challenges (jti, account_id, expires_on)
+                 VALUES ($1, $2, NOW() + $3 * INTERVAL '1 second')",
+        )
+        .bind(jti)
+        .bind(account_id.0)
+        .bind(ttl_seconds)
+        .execute(&self.db_pool)
+        .await;
+
+        match result {
+            Ok(_) => Ok(true),
+            Err(e) => Err(Error::DatabaseQueryError(e)),
+        }
+    }
+
+    // a challenge is good for a single attempt, used or not it is gone
+    async fn use_login_challenge(&self, jti: &str) -> Result<AccountId, Error> {
+        let result = sqlx::query(
+            "DELETE FROM public.login_challenges
+              WHERE jti = $1 AND expires_on > NOW()
+          RETURNING account_id",
+        )
+        .bind(jti)
+        .map(|row: PgRow| AccountId(row.get("account_id")))
+        .fetch_one(&self.db_pool)
+        .await;
+
+        match result {
+            Ok(account_id) => Ok(account_id),
+            Err(e) => Err(Error::DatabaseQueryError(e)),
+        }
+    }
+
+    async fn add_oidc_login(
+        &self,
+        state_hash: &str,
+        login: &OidcLogin,
+        ttl_seconds: i64,
+    ) -> Result<bool, Error> {
+        // logins abandoned at the identity provider are never redeemed
+        let purged = sqlx::query("DELETE FROM public.oidc_logins WHERE expires_on <= NOW()")
+            .execute(&self.db_pool)
+            .await;
+
+        if let Err(e) = purged {
+            return Err(Error::DatabaseQueryError(e));
+        }
+
+        let result = sqlx::query(
+            "INSERT INTO public.oidc_logins (state_hash, code_verifier, nonce, expires_on)
+                 VALUES ($1, $2, $3, NOW() + $4 * INTERVAL '1 second')",
+        )
+        .bind(state_hash)
+        .bind(&login.code_verifier)
+        .bind(&login.nonce)
+        .bind(ttl_seconds as f64)
+        .execute(&self.db_pool)
+        .await;
+
+        match result {
+            Ok(_) => Ok(true),
+            Err(e) => Err(Error::DatabaseQueryError(e)),
+        }
+    }
+
+    // a state is redeemed once, whether the login then succeeds or not
+    async fn use_oidc_login(&self, state_hash: &str) -> Result<OidcLogin, Error> {
+        let result = sqlx::query(
+            "DELETE FROM public.oidc_logins
+              WHERE state_hash = $1 AND expires_on > NOW()
+          RETURNING code_verifier, nonce",
+        )
+        .bind(state_hash)
+        .map(|row: PgRow| OidcLogin {
+            code_verifier: row.get("code_verifier"),
+            nonce: row.get("nonce"),
+        })
+        .fetch_one(&self.db_pool)
+        .await;
+
+        match result {
+            Ok(login) => Ok(login),
+            Err(e) => Err(Error::DatabaseQueryError(e)),
+        }
+    }
+
+    async fn get_account_by_identity(&self, issuer: &str, subject: &str) -> Result<Account, Error> {
+        match sqlx::query(
+            "SELECT a.*
+               FROM public.accounts a
+               JOIN public.account_identities i ON i.account_id = a.id
+              WHERE i.issuer = $1 AND i.subject = $2",
+        )
+        .bind(issuer)
+        .bind(subject)
+        .map(|row: PgRow| Account {
+            id: AccountId(row.get("id")),
+            email: row.get("email"),
+            password: PasswordHash::new(row.get("password")),
+            role: Role::from(row.get::<&str, _>("role")),
+            two_factor_enabled: row
+                .get::<Option<NaiveDateTime>, _>("totp_enabled_at")
+                .is_some(),
+            has_password: row.get("password_set"),
+        })
+        .fetch_one(&self.db_pool)
+        .await
+        {
+            Ok(account) => Ok(account),
+            Err(error) => Err(Error::DatabaseQueryError(error)),
+        }
+    }
+
+    // links the identity to the account with the same email when the provider
+    // verified that email, otherwise creates a new account for it. None when
+    // the email is unverified and already used by another account. Concurrent
+    // first logins of the same subject all end up on one account.
+    async fn add_account_identity(
+        &self,
+        identity: &ExternalIdentity,
+        email: &str,
+        password: &PasswordHash,
+    ) -> Result<Option<AccountId>, Error> {
+        let mut tx = self
+            .db_pool
+            .begin()
+            .await
+            .map_err(Error::DatabaseQueryError)?;
+
+        // waits for a concurrent insert of the same email to commit or roll back
+        let created = sqlx::query(
+            "INSERT INTO public.accounts (email, password, password_set, verified_at)
+                  VALUES ($1, $2, FALSE, CASE WHEN $3 THEN NOW() END)
+             ON CONFLICT (email) DO NOTHING
+               RETURNING id",
+        )
+        .bind(email)
+        .bind(password.as_str())
+        .bind(identity.email_verified)
+        .map(|row: PgRow| AccountId(row.get("id")))
+        .fetch_optional(&mut tx)
+        .await
+        .map_err(Error::DatabaseQueryError)?;
+
+        let account_id = match created {
+            Some(account_id) => account_id,
+            None => {
+                // the same subject may have just been linked by a concurrent login
+                let linked = sqlx::query(
+                    "SELECT account_id FROM public.account_identities
+                      WHERE issuer = $1 AND subject = $2",
+                )
+                .bind(&identity.issuer)
+                .bind(&identity.subject)
+                .map(|row: PgRow| AccountId(row.get("account_id")))
+                .fetch_optional(&mut tx)
+                .await
+                .map_err(Error::DatabaseQueryError)?;
+
+                match linked {
+                    Some(account_id) => return Ok(Some(account_id)),
+                    None if !identity.email_verified => return Ok(None),
+                    None => sqlx::query("SELECT id FROM public.accounts WHERE email = $1")
+                        .bind(email)
+                        .map(|row: PgRow| AccountId(row.get("id")))
+                        .fetch_one(&mut tx)
+                        .await
+                        .map_err(Error::DatabaseQueryError)?,
+                }
+            }
+        };
+
+        let linked = sqlx::query(
+            "INSERT INTO public.account_identities (account_id, issuer, subject)
+                  VALUES ($1, $2, $3)
+             ON CONFLICT (issuer, subject) DO NOTHING
+               RETURNING account_id",
+        )
+        .bind(account_id.0)
+        .bind(&identity.issuer)
+        .bind(&identity.subject)
+        .fetch_optional(&mut tx)
+        .await
+        .map_err(Error::DatabaseQueryError)?;
+
+        if linked.is_none() {
+            // another login linked the subject first: drop the account created
+            // here, if any, and use theirs
+            tx.rollback().await.map_err(Error::DatabaseQueryError)?;
+            let account = self
+                .get_account_by_identity(&identity.issuer, &identity.subject)
+                .await?;
+            return Ok(Some(account.id));
+        }
+
+        tx.commit().await.map_err(Error::DatabaseQueryError)?;
+
+        Ok(Some(account_id))
+    }
+
+    async fn add_refresh_token(
+        &self,
+        account_id: AccountId,
+        family_id: &str,
+        token_hash: &str,
+        ttl_seconds: i64,
+        scopes: Option<Vec<String>>,
+    ) -> Result<bool, Error> {
+        let result = sqlx::query(
+            "INSERT INTO public.refresh_tokens
+                        (account_id, family_id, token_hash, expires_on, scopes)
+                 VALUES ($1, $2, $3, NOW() + $4 * INTERVAL '1 second', $5)",
+        )
+        .bind(account_id.0)
+        .bind(family_id)
+        .bind(token_hash)
+        .bind(ttl_seconds as f64)
+        .bind(scopes)
+        .execute(&self.db_pool)
+        .await;
+
+        match result {
+            Ok(_) => Ok(true),
+            Err(e) => Err(Error::DatabaseQueryError(e)),
+        }
+    }
+
+    async fn get_refresh_token(&self, token_hash: &str) -> Result<RefreshToken, Error> {
+        let result = sqlx::query(
+            "SELECT id, account_id, family_id,
+                    expires_on <= NOW() AS expired,
+                    used_on IS NOT NULL AS used,
+                    revoked_on IS NOT NULL AS revoked,
+                    scopes
+               FROM public.refresh_tokens
+              WHERE token_hash = $1",
+        )
+        .bind(token_hash)
+        .map(|row: PgRow| RefreshToken {
+            id: row.get("id"),
+            account_id: AccountId(row.get("account_id")),
+            family_id: row.get("family_id"),
+            expired: row.get("expired"),
+            used: row.get("used"),
+            revoked: row.get("revoked"),
+            scopes: row
+                .get::<Option<Vec<String>>, _>("scopes")
+                .map(|scopes| scopes.iter().filter_map(|scope| Scope::parse(scope)).collect()),
+        })
+        .fetch_one(&self.db_pool)
+        .await;
+
+        match result {
+            Ok(token) => Ok(token),
+            Err(e) => Err(Error::DatabaseQueryError(e)),
+        }
+    }
+
+    async fn rotate_refresh_token(
+        &self,
+        current: &RefreshToken,
+        token_hash: &str,
+        ttl_seconds: i64,
+    ) -> Result<bool, Error> {
+        let mut tx = self
+            .db_pool
+            .begin()
+            .await
+            .map_err(Error::DatabaseQueryError)?;
+
+        // only one request can mark the token as used; a concurrent refresh with
+        // the same token finds no row to update and is treated as a reuse.
+        let used = sqlx::query(
+            "UPDATE public.refresh_tokens SET used_on = NOW()
+              WHERE id = $1 AND used_on IS NULL AND revoked_on IS NULL",
+        )
+        .bind(current.id)
+        .execute(&mut tx)
+        .await
+        .map_err(Error::DatabaseQueryError)?;
+
+        if used.rows_affected() != 1 {
+            tx.rollback().await.map_err(Error::DatabaseQueryError)?;
+            return Ok(false);
+        }
+
+        // the new token keeps whatever scopes the family was started with
+        sqlx::query(
+            "INSERT INTO public.refresh_tokens
+                        (account_id, family_id, token_hash, expires_on, scopes)
+                 SELECT account_id, family_id, $2, NOW() + $3 * INTERVAL '1 second', scopes
+                   FROM public.refresh_tokens
+                  WHERE id = $1",
+        )
+        .bind(current.id)
+        .bind(token_hash)
+        .bind(ttl_seconds as f64)
+        .execute(&mut tx)
+        .await
+        .map_err(Error::DatabaseQueryError)?;
+
+        tx.commit().await.map_err(Error::DatabaseQueryError)?;
+
+        Ok(true)
+    }
+
+    // the session record goes with the family, signing the device out
+    async fn revoke_refresh_token_family(&self, family_id: &str) -> Result<bool, Error> {
+        let result = sqlx::query(
+            "WITH revoked AS (
+                 UPDATE public.refresh_tokens SET revoked_on = NOW()
+                  WHERE family_id = $1 AND revoked_on IS NULL
+             )
+             DELETE FROM public.sessions WHERE family_id = $1",
+        )
+        .bind(family_id)
+        .execute(&self.db_pool)
+        .await;
+
+        match result {
+            Ok(_) => Ok(true),
+            Err(e) => Err(Error::DatabaseQueryError(e)),
+        }
+    }
+
+    async fn revoke_account_refresh_tokens(
+        &self,
+        account_id: AccountId,
+        keep_family_id: Option<&str>,
+    ) -> Result<bool, Error> {
+        let result = sqlx::query(
+            "WITH revoked AS (
+                 UPDATE public.refresh_tokens SET revoked_on = NOW()
+                  WHERE account_id = $1
+                    AND family_id IS DISTINCT FROM $2
+                    AND revoked_on IS NULL
+             )
+             DELETE FROM public.sessions
+              WHERE account_id = $1
+                AND family_id IS DISTINCT FROM $2",
+        )
+        .bind(account_id.0)
+        .bind(keep_family_id)
+        .execute(&self.db_pool)
+        .await;
+
+        match result {
+            Ok(_) => Ok(true),
+            Err(e) => Err(Error::DatabaseQueryError(e)),
+        }
+    }
+
+    async fn add_session(
+        &self,
+        account_id: AccountId,
+        family_id: &str,
+        client: &ClientInfo,
+        method: LoginMethod,
+    ) -> Result<bool, Error> {
+        let result = sqlx::query(
+            "INSERT INTO public.sessions (account_id, family_id, user_agent, ip_address, login_method)
+                  VALUES ($1, $2, LEFT($3, 512), $4, $5)",
+        )
+        .bind(account_id.0)
+        .bind(family_id)
+        .bind(&client.user_agent)
+        .bind(client.ip.map(|ip| ip.to_string()))
+        .bind(method.as_str())
+        .execute(&self.db_pool)
+        .await;
+
+        match result {
+            Ok(_) => Ok(true),
+            Err(e) => Err(Error::DatabaseQueryError(e)),
+        }
+    }
+
+    // whether the session was logged into with `method` at most `max_age_seconds` ago
+    async fn is_recent_login(
+        &self,
+        family_id: &str,
+        method: LoginMethod,
+        max_age_seconds: i64,
+    ) -> Result<bool, Error> {
+        let result = sqlx::query(
+            "SELECT EXISTS (
+                 SELECT 1 FROM public.sessions
+                  WHERE family_id = $1
+                    AND login_method = $2
+                    AND created_on > NOW() - $3 * INTERVAL '1 second'
+             ) AS recent",
+        )
+        .bind(family_id)
+        .bind(method.as_str())
+        .bind(max_age_seconds as f64)
+        .map(|row: PgRow| row.get::<bool, _>("recent"))
+        .fetch_one(&self.db_pool)
+        .await;
+
+        match result {
+            Ok(recent) => Ok(recent),
+            Err(e) => Err(Error::DatabaseQueryError(e)),
+        }
+    }
+
+    // false once the session was signed out
+    async fn touch_session(&self, family_id: &str) -> Result<bool, Error> {
+        // every authenticated request ends up here, most of them only read
+        let result = sqlx::query(
+            "WITH session AS (
+                 SELECT id, last_seen_on FROM public.sessions WHERE family_id = $1
+             ), touched AS (
+                 UPDATE public.sessions SET last_seen_on = NOW()
+                  WHERE id IN (SELECT id FROM session)
+                    AND last_seen_on < NOW() - $2 * INTERVAL '1 second'
+             )
+             SELECT id FROM session",
+        )
+        .bind(family_id)
+        .bind(LAST_SEEN_PRECISION_SECONDS)
+        .fetch_optional(&self.db_pool)
+        .await;
+
+        match result {
+            Ok(session) => Ok(session.is_some()),
+            Err(e) => Err(Error::DatabaseQueryError(e)),
+        }
+    }
+
+    async fn get_sessions(
+        &self,
+        account_id: AccountId,
+        current_family_id: &str,
+    ) -> Result<Vec<ActiveSession>, Error> {
+        // a family whose last refresh token expired can't be resumed, the
+        // device is signed out already
+        let purged = sqlx::query(
+            "DELETE FROM public.sessions s
+              WHERE s.account_id = $1
+                AND NOT EXISTS (
+                    SELECT 1 FROM public.refresh_tokens r
+                     WHERE r.family_id = s.family_id
+                       AND r.used_on IS NULL
+                       AND r.revoked_on IS NULL
+                       AND r.expires_on > NOW()
+                )",
+        )
+        .bind(account_id.0)
+        .execute(&self.db_pool)
+        .await;
+
+        if let Err(e) = purged {
+            return Err(Error::DatabaseQueryError(e));
+        }
+
+        let result = sqlx::query(
+            "SELECT id, user_agent, ip_address, created_on, last_seen_on,
+                    family_id = $2 AS current
+               FROM public.sessions
+              WHERE account_id = $1
+              ORDER BY last_seen_on DESC",
+        )
+        .bind(account_id.0)
+        .bind(current_family_id)
+        .map(|row: PgRow| ActiveSession {
+            id: row.get("id"),
+            user_agent: row.get("user_agent"),
+            ip_address: row.get("ip_address"),
+            created_on: row.get("created_on"),
+            last_seen_on: row.get("last_seen_on"),
+            current: row.get("current"),
+        })
+        .fetch_all(&self.db_pool)
+        .await;
+
+        match result {
+            Ok(sessions) => Ok(sessions),
+            Err(e) => Err(Error::DatabaseQueryError(e)),
+        }
+    }
+
+    // scoped to the owner, someone else's session is reported as not found
+    async fn delete_session(&self, account_id: AccountId, session_id: i32) -> Result<bool, Error> {
+        let result = sqlx::query(
+            "WITH deleted AS (
+                 DELETE FROM public.sessions
+                  WHERE id = $1 AND account_id = $2
+              RETURNING family_id
+             ), revoked AS (
+                 UPDATE public.refresh_tokens SET revoked_on = NOW()
+                   FROM deleted
+                  WHERE refresh_tokens.family_id = deleted.family_id
+                    AND refresh_tokens.revoked_on IS NULL
+             )
+             SELECT family_id FROM deleted",
+        )
+        .bind(session_id)
+        .bind(account_id.0)
+        .fetch_one(&self.db_pool)
+        .await;
+
+        match result {
+            Ok(_) => Ok(true),
+            Err(e) => Err(Error::DatabaseQueryError(e)),
+        }
+    }
+
+    async fn add_api_key(
+        &self,
+        account_id: AccountId,
+        name: &str,
+        prefix: &str,
+        key_hash: &str,
+        scopes: Vec<String>,
+        ttl_seconds: Option<i64>,
+    ) -> Result<ApiKey, Error> {
+        let result = sqlx::query(
+            "INSERT INTO public.api_keys (account_id, name, prefix, key_hash, scopes, expires_on)
+                  VALUES ($1, $2, $3, $4, $5, NOW() + $6 * INTERVAL '1 second')
+               RETURNING id, name, prefix, scopes, expires_on, last_used_on, created_on",
+        )
+        .bind(account_id.0)
+        .bind(name)
+        .bind(prefix)
+        .bind(key_hash)
+        .bind(scopes)
+        .bind(ttl_seconds.map(|ttl_seconds| ttl_seconds as f64))
+        .map(api_key_from_row)
+        .fetch_one(&self.db_pool)
+        .await;
+
+        match result {
+            Ok(api_key) => Ok(api_key),
+            Err(e) => Err(Error::DatabaseQueryError(e)),
+        }
+    }
+
+    async fn get_api_keys(&self, account_id: AccountId) -> Result<Vec<ApiKey>, Error> {
+        let result = sqlx::query(
+            "SELECT id, name, prefix, scopes, expires_on, last_used_on, created_on
+               FROM public.api_keys
+              WHERE account_id = $1
+                AND revoked_on IS NULL
+              ORDER BY created_on",
+        )
+        .bind(account_id.0)
+        .map(api_key_from_row)
+        .fetch_all(&self.db_pool)
+        .await;
+
+        match result {
+            Ok(api_keys) => Ok(api_keys),
+            Err(e) => Err(Error::DatabaseQueryError(e)),
+        }
+    }
+
+    // scoped to the owner, someone else's key is reported as not found
+    async fn revoke_api_key(&self, account_id: AccountId, api_key_id: ApiKeyId) -> Result<bool, Error> {
+        let result = sqlx::query(
+            "UPDATE public.api_keys SET revoked_on = NOW()
+              WHERE id = $1
+                AND account_id = $2
+                AND revoked_on IS NULL
+          RETURNING id",
+        )
+        .bind(api_key_id.0)
+        .bind(account_id.0)
+        .fetch_one(&self.db_pool)
+        .await;
+
+        match result {
+            Ok(_) => Ok(true),
+            Err(e) => Err(Error::DatabaseQueryError(e)),
+        }
+    }
+
+    async fn use_api_key(&self, key_hash: &str) -> Result<ApiKeyOwner, Error> {
+        let result = sqlx::query(
+            "WITH used AS (
+                UPDATE public.api_keys SET last_used_on = NOW()
+                 WHERE key_hash = $1
+                   AND revoked_on IS NULL
+                   AND (expires_on IS NULL OR expires_on > NOW())
+             RETURNING id, account_id, scopes, expires_on
+             )
+             SELECT u.id, u.account_id, u.scopes, u.expires_on, a.role
+               FROM used u
+               JOIN public.accounts a ON a.id = u.account_id",
+        )
+        .bind(key_hash)
+        .map(|row: PgRow| ApiKeyOwner {
+            id: ApiKeyId(row.get("id")),
+            account_id: AccountId(row.get("account_id")),
+            role: Role::from(row.get::<&str, _>("role")),
+            scopes: scopes_from_row(&row),
+            expires_on: row.get("expires_on"),
+        })
+        .fetch_one(&self.db_pool)
+        .await;
+
+        match result {
+            Ok(owner) => Ok(owner),
+            Err(e) => Err(Error::DatabaseQueryError(e)),
+        }
+    }
+
+    async fn revoke_token(&self, jti: &str, expires_on: DateTime<Utc>) -> Result<bool, Error> {
+        let result = sqlx::query(
+            "INSERT INTO public.revoked_tokens (jti, expires_on)
+                 VALUES ($1, $2)
+            ON CONFLICT (jti) DO NOTHING",
+        )
+        .bind(jti)
+        .bind(expires_on)
+        .execute(&self.db_pool)
+        .await;
+
+        match result {
+            Ok(_) => Ok(true),
+            Err(e) => Err(Error::DatabaseQueryError(e)),
+        }
+    }
+
+    async fn get_revoked_tokens(&self) -> Result<Vec<RevokedToken>, Error> {
+        let result = sqlx::query(
+            "SELECT jti, expires_on
+               FROM public.revoked_tokens
+              WHERE expires_on > NOW()",
+        )
+        .map(|row: PgRow| RevokedToken {
+            jti: row.get("jti"),
+            expires_on: row.get("expires_on"),
+        })
+        .fetch_all(&self.db_pool)
+        .await;
+
+        match result {
+            Ok(tokens) => Ok(tokens),
+            Err(e) => Err(Error::DatabaseQueryError(e)),
+        }
+    }
+
+    async fn purge_revoked_tokens(&self) -> Result<bool, Error> {
+        let result = sqlx::query("DELETE FROM public.revoked_tokens WHERE expires_on <= NOW()")
+            .execute(&self.db_pool)
+            .await;
+
+        match result {
+            Ok(_) => Ok(true),
+            Err(e) => Err(Error::DatabaseQueryError(e)),
         }
     }
 
@@ -69,44 +1392,51 @@ impl DatabaseRepository for Repository {
         &self,
         question: QuestionDTO,
         account_id: AccountId,
+        points: ReputationPoints,
     ) -> Result<Question, Error> {
-        match sqlx::query(
-            "INSERT INTO public.questions
+        let mut tx = self
+            .db_pool
+            .begin()
+            .await
+            .map_err(Error::DatabaseQueryError)?;
+
+        let question = sqlx::query(
+            "WITH q AS (
+                 INSERT INTO public.questions
                              (title, content, tags, account_id)
                       VALUES ($1, $2, $3, $4)
-                      RETURNING id, title, content, tags",
+                      RETURNING id, title, content, tags, score, account_id
+             )
+             SELECT q.*, a.display_name, a.avatar_url
+               FROM q
+               LEFT JOIN public.accounts a ON a.id = q.account_id",
         )
         .bind(question.title)
         .bind(question.content)
         .bind(question.tags)
         .bind(account_id.0)
-        .map(|row: PgRow| Question {
-            id: QuestionId(row.get("id")),
-            title: row.get("title"),
-            content: row.get("content"),
-            tags: row.get("tags"),
-        })
-        .fetch_one(&self.db_pool)
+        .map(question_from_row)
+        .fetch_one(&mut tx)
         .await
-        {
-            Ok(question) => Ok(question),
-            Err(e) => Err(Error::DatabaseQueryError(e)),
-        }
+        .map_err(Error::DatabaseQueryError)?;
+
+        record_reputation(&mut tx, account_id.0, ReputationEvent::QuestionAsked, 1, &points, question.id.0, None).await?;
+
+        tx.commit().await.map_err(Error::DatabaseQueryError)?;
+
+        Ok(question)
     }
 
     async fn get_question(&self, question_id: QuestionId) -> Result<Question, Error> {
         let result = sqlx::query(
-            "SELECT id, title, content, tags, account_id, created_on
-                   FROM public.questions
-                  WHERE id = $1",
+            "SELECT q.id, q.title, q.content, q.tags, q.score, q.account_id, q.created_on,
+                    a.display_name, a.avatar_url
+                   FROM public.questions q
+                   LEFT JOIN public.accounts a ON a.id = q.account_id
+                  WHERE q.id = $1",
         )
         .bind(question_id.0)
-        .map(|row: PgRow| Question {
-            id: question_id,
-            title: row.get("title"),
-            content: row.get("content"),
-            tags: row.get("tags"),
-        })
+        .map(question_from_row)
         .fetch_one(&self.db_pool)
         .await;
         match result {
@@ -115,26 +1445,114 @@ impl DatabaseRepository for Repository {
         }
     }
 
+    // a row per answer, or a single row with null answer columns when there
+    // is none, each carrying the question. The comments come separately,
+    // joining them in would repeat every answer once per comment.
+    async fn get_question_with_answers(&self, question_id: QuestionId) -> Result<QuestionDetail, Error> {
+        let result = sqlx::query(
+            "SELECT q.id, q.title, q.content, q.tags, q.score, q.account_id, q.created_on,
+                    a.display_name, a.avatar_url,
+                    n.id AS answer_id, n.content AS answer_content, n.score AS answer_score,
+                    n.account_id AS answer_account_id,
+                    COALESCE(q.accepted_answer_id = n.id, false) AS answer_is_accepted,
+                    na.display_name AS answer_display_name,
+                    na.avatar_url AS answer_avatar_url
+               FROM public.questions q
+               LEFT JOIN public.accounts a ON a.id = q.account_id
+               LEFT JOIN public.answers n ON n.question_id = q.id
+               LEFT JOIN public.accounts na ON na.id = n.account_id
+              WHERE q.id = $1
+              ORDER BY answer_is_accepted DESC, n.created_on, n.id",
+        )
+        .bind(question_id.0)
+        .fetch_all(&self.db_pool)
+        .await;
+
+        let rows = match result {
+            Ok(rows) => rows,
+            Err(e) => return Err(Error::DatabaseQueryError(e)),
+        };
+
+        let question = match rows.first() {
+            Some(row) => Question {
+                id: QuestionId(row.get("id")),
+                title: row.get("title"),
+                content: row.get("content"),
+                tags: row.get("tags"),
+                score: row.get("score"),
+                author: author_from_row(row),
+            },
+            None => return Err(Error::DatabaseQueryError(sqlx::Error::RowNotFound)),
+        };
+
+        let comments = sqlx::query(
+            "SELECT c.id, c.content, c.account_id, c.question_id, c.answer_id,
+                    c.created_on, c.updated_on, a.display_name, a.avatar_url
+               FROM public.comments c
+               LEFT JOIN public.accounts a ON a.id = c.account_id
+              WHERE c.question_id = $1
+                 OR c.answer_id IN (SELECT id FROM public.answers WHERE question_id = $1)
+              ORDER BY c.created_on, c.id",
+        )
+        .bind(question_id.0)
+        .map(comment_from_row)
+        .fetch_all(&self.db_pool)
+        .await
+        .map_err(Error::DatabaseQueryError)?;
+
+        let mut question_comments = Vec::new();
+        let mut answer_comments: HashMap<i32, Vec<Comment>> = HashMap::new();
+        for comment in comments {
+            match comment.answer_id {
+                Some(answer_id) => answer_comments.entry(answer_id.0).or_default().push(comment),
+                None => question_comments.push(comment),
+            }
+        }
+
+        let answers = rows
+            .iter()
+            .filter_map(|row| {
+                let id = row.get::<Option<i32>, _>("answer_id")?;
+                Some(Answer {
+                    id: AnswerId(id),
+                    content: row.get("answer_content"),
+                    score: row.get("answer_score"),
+                    question_id: question.id,
+                    is_accepted: row.get("answer_is_accepted"),
+                    author: row.get::<Option<i32>, _>("answer_account_id").map(|id| Author {
+                        id: AccountId(id),
+                        display_name: row.get("answer_display_name"),
+                        avatar_url: row.get("answer_avatar_url"),
+                    }),
+                    comments: Some(answer_comments.remove(&id).unwrap_or_default()),
+                })
+            })
+            .collect();
+
+        Ok(QuestionDetail {
+            question,
+            answers: Some(answers),
+            comments: Some(question_comments),
+        })
+    }
+
     async fn get_questions(
         &self,
         limit: Option<i16>,
         offset: i16,
     ) -> Result<Vec<Question>, Error> {
         let result = sqlx::query(
-            "SELECT id, title, content, tags, account_id, created_on
-                   FROM public.questions
-                  ORDER BY created_on desc
+            "SELECT q.id, q.title, q.content, q.tags, q.score, q.account_id, q.created_on,
+                    a.display_name, a.avatar_url
+                   FROM public.questions q
+                   LEFT JOIN public.accounts a ON a.id = q.account_id
+                  ORDER BY q.created_on desc
                   LIMIT $1
                  OFFSET $2",
         )
         .bind(limit)
         .bind(offset)
-        .map(|row: PgRow| Question {
-            id: QuestionId(row.get("id")),
-            title: row.get("title"),
-            content: row.get("content"),
-            tags: row.get("tags"),
-        })
+        .map(question_from_row)
         .fetch_all(&self.db_pool)
         .await;
 
@@ -149,23 +1567,22 @@ impl DatabaseRepository for Repository {
         question: QuestionDTO,
         question_id: QuestionId,
     ) -> Result<Question, Error> {
-        // the update can only be applied when the user is the entry's owner.
-        // checked by "is_question_owner"
+        // who may edit is checked by the controller, see `update_question` there
         let result = sqlx::query(
-            "UPDATE questions SET title = $1, content = $2, tags = $3
-        WHERE id = $4
-        RETURNING id, title, content, tags",
+            "WITH q AS (
+                 UPDATE questions SET title = $1, content = $2, tags = $3
+                  WHERE id = $4
+                  RETURNING id, title, content, tags, score, account_id
+             )
+             SELECT q.*, a.display_name, a.avatar_url
+               FROM q
+               LEFT JOIN public.accounts a ON a.id = q.account_id",
         )
         .bind(question.title)
         .bind(question.content)
         .bind(question.tags)
         .bind(question_id.0)
-        .map(|row: PgRow| Question {
-            id: question_id,
-            title: row.get("title"),
-            content: row.get("content"),
-            tags: row.get("tags"),
-        })
+        .map(question_from_row)
         .fetch_one(&self.db_pool)
         .await;
 
@@ -177,18 +1594,32 @@ impl DatabaseRepository for Repository {
 
     async fn delete_question(&self, question_id: QuestionId) -> Result<bool, Error> {
         // the update can only be applied when the user is the entry's owner.
-        // checked by "is_question_owner"
-        let result = sqlx::query(
+        // checked by "is_question_owner". Its answers are deleted along (on delete cascade).
+        let mut tx = self
+            .db_pool
+            .begin()
+            .await
+            .map_err(Error::DatabaseQueryError)?;
+
+        let deleted = sqlx::query(
             "DELETE FROM public.questions
             WHERE id = $1",
         )
         .bind(question_id.0)
-        .execute(&self.db_pool)
-        .await;
-        match result {
-            Ok(_) => Ok(true),
-            Err(e) => Err(Error::DatabaseQueryError(e)),
+        .execute(&mut tx)
+        .await
+        .map_err(Error::DatabaseQueryError)?;
+
+        if deleted.rows_affected() == 0 {
+            return Err(Error::DatabaseQueryError(sqlx::Error::RowNotFound));
         }
+
+        // answer events carry their question's id too
+        reverse_reputation(&mut tx, "question_id = $1", question_id.0).await?;
+
+        tx.commit().await.map_err(Error::DatabaseQueryError)?;
+
+        Ok(true)
     }
 
     async fn is_question_owner(
@@ -209,6 +1640,147 @@ impl DatabaseRepository for Repository {
         }
     }
 
+    async fn get_answer(&self, answer_id: AnswerId) -> Result<Answer, Error> {
+        let result = sqlx::query(
+            "SELECT n.id, n.content, n.score, n.account_id, n.question_id,
+                    COALESCE(q.accepted_answer_id = n.id, false) AS is_accepted,
+                    a.display_name, a.avatar_url
+               FROM public.answers n
+               LEFT JOIN public.questions q ON q.id = n.question_id
+               LEFT JOIN public.accounts a ON a.id = n.account_id
+              WHERE n.id = $1",
+        )
+        .bind(answer_id.0)
+        .map(answer_from_row)
+        .fetch_one(&self.db_pool)
+        .await;
+
+        match result {
+            Ok(answer) => Ok(answer),
+            Err(e) => Err(Error::DatabaseQueryError(e)),
+        }
+    }
+
+    async fn get_answers(
+        &self,
+        question_id: QuestionId,
+        sort: AnswerSort,
+        limit: Option<i16>,
+        offset: i16,
+    ) -> Result<Vec<Answer>, Error> {
+        // the accepted answer comes first whatever the order,
+        // the id breaks ties so pages don't overlap
+        let order = match sort {
+            AnswerSort::Newest => "n.created_on DESC, n.id DESC",
+            AnswerSort::Oldest => "n.created_on, n.id",
+            AnswerSort::Score => "n.score DESC, n.created_on, n.id",
+        };
+
+        let result = sqlx::query(&format!(
+            "SELECT n.id, n.content, n.score, n.account_id, n.question_id,
+                    COALESCE(q.accepted_answer_id = n.id, false) AS is_accepted,
+                    a.display_name, a.avatar_url
+               FROM public.answers n
+               LEFT JOIN public.questions q ON q.id = n.question_id
+               LEFT JOIN public.accounts a ON a.id = n.account_id
+              WHERE n.question_id = $1
+              ORDER BY is_accepted DESC, {}
+              LIMIT $2
+             OFFSET $3",
+            order
+        ))
+        .bind(question_id.0)
+        .bind(limit)
+        .bind(offset)
+        .map(answer_from_row)
+        .fetch_all(&self.db_pool)
+        .await;
+
+        match result {
+            Ok(answers) => Ok(answers),
+            Err(e) => Err(Error::DatabaseQueryError(e)),
+        }
+    }
+
+    // only an answer to the question itself can be accepted. Accepting your
+    // own answer earns nothing.
+    async fn accept_answer(
+        &self,
+        question_id: QuestionId,
+        answer_id: AnswerId,
+        points: ReputationPoints,
+    ) -> Result<bool, Error> {
+        let mut tx = self
+            .db_pool
+            .begin()
+            .await
+            .map_err(Error::DatabaseQueryError)?;
+
+        let question_author = sqlx::query(
+            "UPDATE public.questions SET accepted_answer_id = $2
+              WHERE id = $1
+                AND EXISTS (SELECT 1 FROM public.answers WHERE id = $2 AND question_id = $1)
+             RETURNING account_id",
+        )
+        .bind(question_id.0)
+        .bind(answer_id.0)
+        .map(|row: PgRow| row.get::<Option<i32>, _>("account_id"))
+        .fetch_one(&mut tx)
+        .await
+        .map_err(Error::DatabaseQueryError)?;
+
+        // whoever had the accepted answer before loses the points
+        reverse_reputation(&mut tx, "question_id = $1 AND kind = 'answer_accepted'", question_id.0).await?;
+
+        let author = sqlx::query("SELECT account_id FROM public.answers WHERE id = $1")
+            .bind(answer_id.0)
+            .map(|row: PgRow| row.get::<Option<i32>, _>("account_id"))
+            .fetch_one(&mut tx)
+            .await
+            .map_err(Error::DatabaseQueryError)?;
+
+        if let Some(author) = author.filter(|author| Some(*author) != question_author) {
+            record_reputation(
+                &mut tx,
+                author,
+                ReputationEvent::AnswerAccepted,
+                1,
+                &points,
+                question_id.0,
+                Some(answer_id.0),
+            )
+            .await?;
+        }
+
+        tx.commit().await.map_err(Error::DatabaseQueryError)?;
+
+        Ok(true)
+    }
+
+    async fn unaccept_answer(&self, question_id: QuestionId) -> Result<bool, Error> {
+        let mut tx = self
+            .db_pool
+            .begin()
+            .await
+            .map_err(Error::DatabaseQueryError)?;
+
+        let updated = sqlx::query("UPDATE public.questions SET accepted_answer_id = NULL WHERE id = $1")
+            .bind(question_id.0)
+            .execute(&mut tx)
+            .await
+            .map_err(Error::DatabaseQueryError)?;
+
+        if updated.rows_affected() == 0 {
+            return Err(Error::DatabaseQueryError(sqlx::Error::RowNotFound));
+        }
+
+        reverse_reputation(&mut tx, "question_id = $1 AND kind = 'answer_accepted'", question_id.0).await?;
+
+        tx.commit().await.map_err(Error::DatabaseQueryError)?;
+
+        Ok(true)
+    }
+
     async fn is_answer_owner(&self, answer_id: AnswerId, account_id: AccountId) -> Result<bool, Error> {
         let result = sqlx::query("select * from public.answers where id = $1 and account_id = $2")
             .bind(answer_id.0)
@@ -226,19 +1798,88 @@ impl DatabaseRepository for Repository {
         &self,
         answer: AnswerDTO,
         account_id: AccountId,
+        points: ReputationPoints,
     ) -> Result<Answer, Error> {
-        let result = sqlx::query("INSERT INTO public.answers
-            (content, account_id, question_id)
-            VALUES($1, $2, $3)
-            returning id, content, account_id, question_id;")
+        let mut tx = self
+            .db_pool
+            .begin()
+            .await
+            .map_err(Error::DatabaseQueryError)?;
+
+        let answer = sqlx::query("WITH new_answer AS (
+                INSERT INTO public.answers
+                (content, account_id, question_id)
+                VALUES($1, $2, $3)
+                returning id, content, score, account_id, question_id
+            )
+            SELECT new_answer.*, false AS is_accepted, a.display_name, a.avatar_url
+              FROM new_answer
+              LEFT JOIN public.accounts a ON a.id = new_answer.account_id")
             .bind(answer.content)
             .bind(account_id.0)
             .bind(answer.question_id.0)
-            .map(|row: PgRow| Answer {
-                id: AnswerId(row.get("id")),
-                content: row.get("content"),
-                question_id: QuestionId(row.get("question_id"))
-            })
+            .map(answer_from_row)
+            .fetch_one(&mut tx)
+            .await
+            .map_err(Error::DatabaseQueryError)?;
+
+        record_reputation(
+            &mut tx,
+            account_id.0,
+            ReputationEvent::AnswerPosted,
+            1,
+            &points,
+            answer.question_id.0,
+            Some(answer.id.0),
+        )
+        .await?;
+
+        tx.commit().await.map_err(Error::DatabaseQueryError)?;
+
+        Ok(answer)
+    }
+
+    async fn delete_answer(&self, answer_id: AnswerId) -> Result<bool, Error> {
+        // checked by "is_answer_owner"
+        let mut tx = self
+            .db_pool
+            .begin()
+            .await
+            .map_err(Error::DatabaseQueryError)?;
+
+        let deleted = sqlx::query("DELETE FROM public.answers WHERE id = $1")
+            .bind(answer_id.0)
+            .execute(&mut tx)
+            .await
+            .map_err(Error::DatabaseQueryError)?;
+
+        if deleted.rows_affected() == 0 {
+            return Err(Error::DatabaseQueryError(sqlx::Error::RowNotFound));
+        }
+
+        reverse_reputation(&mut tx, "answer_id = $1", answer_id.0).await?;
+
+        tx.commit().await.map_err(Error::DatabaseQueryError)?;
+
+        Ok(true)
+    }
+
+    async fn update_answer(&self, answer: AnswerDTO, answer_id: AnswerId) -> Result<Answer, Error> {
+        let result = sqlx::query("WITH updated AS (
+                UPDATE public.answers
+                SET content=$1
+                WHERE id=$2
+                returning id, content, score, account_id, question_id
+            )
+            SELECT updated.*,
+                   COALESCE(q.accepted_answer_id = updated.id, false) AS is_accepted,
+                   a.display_name, a.avatar_url
+              FROM updated
+              LEFT JOIN public.questions q ON q.id = updated.question_id
+              LEFT JOIN public.accounts a ON a.id = updated.account_id")
+            .bind(answer.content)
+            .bind(answer_id.0)
+            .map(answer_from_row)
             .fetch_one(&self.db_pool)
             .await;
 
@@ -248,24 +1889,388 @@ impl DatabaseRepository for Repository {
         }
     }
 
-    async fn update_answer(&self, answer: AnswerDTO, answer_id: AnswerId) -> Result<Answer, Error> {
-        let result = sqlx::query("UPDATE public.answers
-            SET content=$1
-            WHERE id=$2
-            returning id, content, account_id, question_id")
-            .bind(answer.content)
-            .bind(answer_id.0)
-            .map(|row: PgRow| Answer {
-                id: AnswerId(row.get("id")),
-                content: row.get("content"),
-                question_id: QuestionId(row.get("question_id"))
-            })
+    // the target's row stays locked until commit, so concurrent votes on it
+    // take turns and each sums up the votes the one before committed.
+    async fn cast_vote(
+        &self,
+        account_id: AccountId,
+        target: PostId,
+        vote: Option<Vote>,
+        points: ReputationPoints,
+    ) -> Result<i32, Error> {
+        let (table, column, id) = match target {
+            PostId::Question(id) => ("questions", "question_id", id.0),
+            PostId::Answer(id) => ("answers", "answer_id", id.0),
+        };
+        let question_column = match target {
+            PostId::Question(_) => "id",
+            PostId::Answer(_) => "question_id",
+        };
+
+        let mut tx = self
+            .db_pool
+            .begin()
+            .await
+            .map_err(Error::DatabaseQueryError)?;
+
+        let (author, question_id) = sqlx::query(&format!(
+            "SELECT account_id, {} AS question_id FROM public.{} WHERE id = $1 FOR UPDATE",
+            question_column, table
+        ))
+        .bind(id)
+        .map(|row: PgRow| (row.get::<Option<i32>, _>("account_id"), row.get::<i32, _>("question_id")))
+        .fetch_one(&mut tx)
+        .await
+        .map_err(Error::DatabaseQueryError)?;
+
+        let previous = sqlx::query(&format!(
+            "SELECT value FROM public.votes WHERE account_id = $1 AND {} = $2",
+            column
+        ))
+        .bind(account_id.0)
+        .bind(id)
+        .map(|row: PgRow| row.get::<i16, _>("value"))
+        .fetch_optional(&mut tx)
+        .await
+        .map_err(Error::DatabaseQueryError)?;
+
+        let statement = match vote {
+            Some(_) => format!(
+                "INSERT INTO public.votes (account_id, {0}, value)
+                 VALUES ($1, $2, $3)
+                 ON CONFLICT (account_id, {0}) DO UPDATE SET value = EXCLUDED.value",
+                column
+            ),
+            None => format!("DELETE FROM public.votes WHERE account_id = $1 AND {} = $2", column),
+        };
+        let mut query = sqlx::query(&statement).bind(account_id.0).bind(id);
+        if let Some(vote) = vote {
+            query = query.bind(vote.value());
+        }
+        let changed = query
+            .execute(&mut tx)
+            .await
+            .map_err(Error::DatabaseQueryError)?;
+
+        // there was no vote to take back
+        if changed.rows_affected() == 0 {
+            return Err(Error::DatabaseQueryError(sqlx::Error::RowNotFound));
+        }
+
+        // the author loses what the previous vote earned and gets what the new one does
+        let answer_id = match target {
+            PostId::Question(_) => None,
+            PostId::Answer(id) => Some(id.0),
+        };
+        let current = vote.map(Vote::value);
+        if let Some(author) = author.filter(|_| previous != current) {
+            if let Some(previous) = previous {
+                record_reputation(&mut tx, author, vote_event(previous), -1, &points, question_id, answer_id).await?;
+            }
+            if let Some(current) = current {
+                record_reputation(&mut tx, author, vote_event(current), 1, &points, question_id, answer_id).await?;
+            }
+        }
+
+        let score = sqlx::query(&format!(
+            "UPDATE public.{} SET score = (
+                 SELECT COALESCE(SUM(value), 0) FROM public.votes WHERE {} = $1
+             )
+             WHERE id = $1
+             RETURNING score",
+            table, column
+        ))
+        .bind(id)
+        .map(|row: PgRow| row.get("score"))
+        .fetch_one(&mut tx)
+        .await
+        .map_err(Error::DatabaseQueryError)?;
+
+        tx.commit().await.map_err(Error::DatabaseQueryError)?;
+
+        Ok(score)
+    }
+
+    async fn get_reputation(&self, account_id: AccountId) -> Result<i32, Error> {
+        let result = sqlx::query("SELECT reputation FROM public.accounts WHERE id = $1")
+            .bind(account_id.0)
+            .map(|row: PgRow| row.get("reputation"))
             .fetch_one(&self.db_pool)
             .await;
 
         match result {
-            Ok(answer) => Ok(answer),
+            Ok(reputation) => Ok(reputation),
+            Err(e) => Err(Error::DatabaseQueryError(e)),
+        }
+    }
+
+    // prices every event in the ledger with `points` and sums them up again.
+    // The table lock keeps new events out until the totals are written.
+    async fn replay_reputation(&self, points: ReputationPoints) -> Result<bool, Error> {
+        let mut tx = self
+            .db_pool
+            .begin()
+            .await
+            .map_err(Error::DatabaseQueryError)?;
+
+        sqlx::query("LOCK TABLE public.reputation_events IN EXCLUSIVE MODE")
+            .execute(&mut tx)
+            .await
+            .map_err(Error::DatabaseQueryError)?;
+
+        sqlx::query(
+            "UPDATE public.reputation_events SET points = units * CASE kind
+                 WHEN $1 THEN $2
+                 WHEN $3 THEN $4
+                 WHEN $5 THEN $6
+                 WHEN $7 THEN $8
+                 WHEN $9 THEN $10
+                 ELSE 0
+             END",
+        )
+        .bind(ReputationEvent::QuestionAsked.kind())
+        .bind(points.question_asked)
+        .bind(ReputationEvent::AnswerPosted.kind())
+        .bind(points.answer_posted)
+        .bind(ReputationEvent::Upvoted.kind())
+        .bind(points.upvoted)
+        .bind(ReputationEvent::Downvoted.kind())
+        .bind(points.downvoted)
+        .bind(ReputationEvent::AnswerAccepted.kind())
+        .bind(points.answer_accepted)
+        .execute(&mut tx)
+        .await
+        .map_err(Error::DatabaseQueryError)?;
+
+        sqlx::query(
+            "UPDATE public.accounts a SET reputation = COALESCE(
+                 (SELECT SUM(points) FROM public.reputation_events WHERE account_id = a.id), 0
+             )",
+        )
+        .execute(&mut tx)
+        .await
+        .map_err(Error::DatabaseQueryError)?;
+
+        tx.commit().await.map_err(Error::DatabaseQueryError)?;
+
+        Ok(true)
+    }
+
+    // nothing is inserted, and RowNotFound returned, when the post doesn't exist
+    async fn create_comment(&self, comment: CommentDTO, account_id: AccountId, post: PostId) -> Result<Comment, Error> {
+        let (question_id, answer_id) = match post {
+            PostId::Question(id) => (Some(id.0), None),
+            PostId::Answer(id) => (None, Some(id.0)),
+        };
+
+        let result = sqlx::query(
+            "WITH c AS (
+                 INSERT INTO public.comments (content, account_id, question_id, answer_id)
+                 SELECT $1, $2, $3, $4
+                  WHERE EXISTS (SELECT 1 FROM public.questions WHERE id = $3)
+                     OR EXISTS (SELECT 1 FROM public.answers WHERE id = $4)
+                 RETURNING id, content, account_id, question_id, answer_id, created_on, updated_on
+             )
+             SELECT c.*, a.display_name, a.avatar_url
+               FROM c
+               LEFT JOIN public.accounts a ON a.id = c.account_id",
+        )
+        .bind(comment.content.trim())
+        .bind(account_id.0)
+        .bind(question_id)
+        .bind(answer_id)
+        .map(comment_from_row)
+        .fetch_one(&self.db_pool)
+        .await;
+
+        match result {
+            Ok(comment) => Ok(comment),
+            Err(e) => Err(Error::DatabaseQueryError(e)),
+        }
+    }
+
+    async fn update_comment(&self, comment: CommentDTO, comment_id: CommentId) -> Result<Comment, Error> {
+        // checked by "is_comment_owner"
+        let result = sqlx::query(
+            "WITH c AS (
+                 UPDATE public.comments SET content = $1, updated_on = NOW()
+                  WHERE id = $2
+                 RETURNING id, content, account_id, question_id, answer_id, created_on, updated_on
+             )
+             SELECT c.*, a.display_name, a.avatar_url
+               FROM c
+               LEFT JOIN public.accounts a ON a.id = c.account_id",
+        )
+        .bind(comment.content.trim())
+        .bind(comment_id.0)
+        .map(comment_from_row)
+        .fetch_one(&self.db_pool)
+        .await;
+
+        match result {
+            Ok(comment) => Ok(comment),
+            Err(e) => Err(Error::DatabaseQueryError(e)),
+        }
+    }
+
+    async fn delete_comment(&self, comment_id: CommentId) -> Result<bool, Error> {
+        let result = sqlx::query("DELETE FROM public.comments WHERE id = $1")
+            .bind(comment_id.0)
+            .execute(&self.db_pool)
+            .await;
+
+        match result {
+            Ok(done) if done.rows_affected() == 0 => Err(Error::DatabaseQueryError(sqlx::Error::RowNotFound)),
+            Ok(_) => Ok(true),
+            Err(e) => Err(Error::DatabaseQueryError(e)),
+        }
+    }
+
+    async fn is_comment_owner(&self, comment_id: CommentId, account_id: AccountId) -> Result<bool, Error> {
+        let result = sqlx::query("SELECT id FROM public.comments WHERE id = $1 AND account_id = $2")
+            .bind(comment_id.0)
+            .bind(account_id.0)
+            .fetch_optional(&self.db_pool)
+            .await;
+
+        match result {
+            Ok(comment) => Ok(comment.is_some()),
             Err(e) => Err(Error::DatabaseQueryError(e)),
         }
     }
 }
+
+// questions and answers are always selected together with their author's
+// display_name and avatar_url, joined from accounts.
+fn author_from_row(row: &PgRow) -> Option<Author> {
+    row.get::<Option<i32>, _>("account_id").map(|id| Author {
+        id: AccountId(id),
+        display_name: row.get("display_name"),
+        avatar_url: row.get("avatar_url"),
+    })
+}
+
+fn question_from_row(row: PgRow) -> Question {
+    Question {
+        id: QuestionId(row.get("id")),
+        title: row.get("title"),
+        content: row.get("content"),
+        tags: row.get("tags"),
+        score: row.get("score"),
+        author: author_from_row(&row),
+    }
+}
+
+fn answer_from_row(row: PgRow) -> Answer {
+    Answer {
+        id: AnswerId(row.get("id")),
+        content: row.get("content"),
+        score: row.get("score"),
+        question_id: QuestionId(row.get("question_id")),
+        is_accepted: row.get("is_accepted"),
+        author: author_from_row(&row),
+        comments: None,
+    }
+}
+
+fn api_key_from_row(row: PgRow) -> ApiKey {
+    ApiKey {
+        id: ApiKeyId(row.get("id")),
+        name: row.get("name"),
+        prefix: row.get("prefix"),
+        scopes: scopes_from_row(&row),
+        expires_on: row.get("expires_on"),
+        last_used_on: row.get("last_used_on"),
+        created_on: row.get("created_on"),
+    }
+}
+
+fn scopes_from_row(row: &PgRow) -> Vec<Scope> {
+    row.get::<Vec<String>, _>("scopes")
+        .iter()
+        .filter_map(|scope| Scope::parse(scope))
+        .collect()
+}
+
+fn comment_from_row(row: PgRow) -> Comment {
+    Comment {
+        id: CommentId(row.get("id")),
+        content: row.get("content"),
+        question_id: row.get::<Option<i32>, _>("question_id").map(QuestionId),
+        answer_id: row.get::<Option<i32>, _>("answer_id").map(AnswerId),
+        author: author_from_row(&row),
+        created_on: row.get("created_on"),
+        updated_on: row.get("updated_on"),
+    }
+}
+
+fn vote_event(value: i16) -> ReputationEvent {
+    if value > 0 {
+        ReputationEvent::Upvoted
+    } else {
+        ReputationEvent::Downvoted
+    }
+}
+
+// appends to the ledger and adds the points to the account's total. `units`
+// is -1 to take back an event recorded before.
+async fn record_reputation(
+    tx: &mut Transaction<'_, Postgres>,
+    account_id: i32,
+    event: ReputationEvent,
+    units: i32,
+    points: &ReputationPoints,
+    question_id: i32,
+    answer_id: Option<i32>,
+) -> Result<(), Error> {
+    sqlx::query(
+        "WITH event AS (
+             INSERT INTO public.reputation_events
+                         (account_id, kind, units, points, question_id, answer_id)
+                  VALUES ($1, $2, $3, $4, $5, $6)
+                  RETURNING account_id, points
+         )
+         UPDATE public.accounts a SET reputation = a.reputation + event.points
+           FROM event
+          WHERE a.id = event.account_id",
+    )
+    .bind(account_id)
+    .bind(event.kind())
+    .bind(units)
+    .bind(units * points.of(event))
+    .bind(question_id)
+    .bind(answer_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(Error::DatabaseQueryError)?;
+
+    Ok(())
+}
+
+// cancels whatever is left of the events `condition` picks (with the id as
+// $1), for posts that are going away. Points are taken back as they were
+// recorded, whatever they are worth now.
+async fn reverse_reputation(tx: &mut Transaction<'_, Postgres>, condition: &str, id: i32) -> Result<(), Error> {
+    sqlx::query(&format!(
+        "WITH reversed AS (
+             INSERT INTO public.reputation_events
+                         (account_id, kind, units, points, question_id, answer_id)
+                  SELECT account_id, kind, -SUM(units), -SUM(points), question_id, answer_id
+                    FROM public.reputation_events
+                   WHERE {}
+                   GROUP BY account_id, kind, question_id, answer_id
+                  HAVING SUM(units) <> 0
+                  RETURNING account_id, points
+         )
+         UPDATE public.accounts a SET reputation = a.reputation + r.points
+           FROM (SELECT account_id, SUM(points) AS points FROM reversed GROUP BY account_id) r
+          WHERE a.id = r.account_id",
+        condition
+    ))
+    .bind(id)
+    .execute(&mut *tx)
+    .await
+    .map_err(Error::DatabaseQueryError)?;
+
+    Ok(())
+}
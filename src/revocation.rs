@@ -0,0 +1,70 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+
+use crate::{
+    custom_errors::repository::Error,
+    repository::{database_repository::DatabaseRepository, Repository},
+};
+
+// how often expired entries are dropped and the cache is resynced with the
+// database, which also picks up tokens revoked by other instances.
+const SYNC_INTERVAL: Duration = Duration::from_secs(60);
+
+// access tokens revoked before their expiry. The table is the source of truth,
+// the cache is what `verify_token` reads so no request pays for a query.
+#[derive(Debug)]
+pub struct RevocationStore {
+    repository: Arc<Repository>,
+    cache: RwLock<HashMap<String, DateTime<Utc>>>,
+}
+
+impl RevocationStore {
+    pub fn new(repository: Arc<Repository>) -> Self {
+        Self {
+            repository,
+            cache: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub async fn revoke(&self, jti: &str, expires_on: DateTime<Utc>) -> Result<bool, Error> {
+        self.repository.revoke_token(jti, expires_on).await?;
+        self.cache
+            .write()
+            .unwrap()
+            .insert(jti.to_string(), expires_on);
+
+        Ok(true)
+    }
+
+    pub fn is_revoked(&self, jti: &str) -> bool {
+        self.cache.read().unwrap().contains_key(jti)
+    }
+
+    pub async fn sync(&self) -> Result<bool, Error> {
+        self.repository.purge_revoked_tokens().await?;
+        let revoked = self.repository.get_revoked_tokens().await?;
+
+        let mut cache = self.cache.write().unwrap();
+        cache.retain(|_, expires_on| *expires_on > Utc::now());
+        cache.extend(revoked.into_iter().map(|t| (t.jti, t.expires_on)));
+
+        Ok(true)
+    }
+
+    pub fn spawn_sync(store: Arc<Self>) {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(SYNC_INTERVAL);
+            loop {
+                interval.tick().await;
+                if let Err(e) = store.sync().await {
+                    eprintln!("cannot sync revoked tokens: {}", e);
+                }
+            }
+        });
+    }
+}
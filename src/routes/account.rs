@@ -0,0 +1,119 @@
+use std::sync::Arc;
+
+use warp::{path, query, Filter, Rejection, Reply};
+
+use crate::{
+    controllers::{account::AccountController, authentication::AuthenticationController},
+    models::{
+        account::{AccountId, Role, RoleUpdate, Session},
+        profile::ProfileUpdate,
+        scope::Scope,
+    },
+};
+
+pub fn get_accounts_route(
+    account_controller: Arc<AccountController>,
+    auth_controller: Arc<AuthenticationController>,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    warp::get()
+        .and(path("accounts"))
+        .and(path::end())
+        .and(auth_controller.require_role(Role::Admin))
+        .and(query())
+        .and_then(move |_session: Session, params| {
+            let controller = account_controller.clone();
+            async move { controller.get_accounts(params).await }
+        })
+}
+
+pub fn update_account_role_route(
+    account_controller: Arc<AccountController>,
+    auth_controller: Arc<AuthenticationController>,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    warp::put()
+        .and(path("accounts"))
+        .and(path::param::<i32>())
+        .and(path("role"))
+        .and(path::end())
+        .and(auth_controller.require_role(Role::Admin))
+        .and(warp::body::json())
+        .and_then(move |id, _session: Session, update: RoleUpdate| {
+            let controller = account_controller.clone();
+            async move { controller.update_role(AccountId(id), update).await }
+        })
+}
+
+pub fn replay_reputation_route(
+    account_controller: Arc<AccountController>,
+    auth_controller: Arc<AuthenticationController>,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    warp::post()
+        .and(path("accounts"))
+        .and(path("reputation"))
+        .and(path("replay"))
+        .and(path::end())
+        .and(auth_controller.require_role(Role::Admin))
+        .and_then(move |_session: Session| {
+            let controller = account_controller.clone();
+            async move { controller.replay_reputation().await }
+        })
+}
+
+pub fn get_own_profile_route(
+    account_controller: Arc<AccountController>,
+    auth_controller: Arc<AuthenticationController>,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    warp::get()
+        .and(path("account"))
+        .and(path("me"))
+        .and(path::end())
+        .and(auth_controller.require_scope(Scope::AccountRead))
+        .and_then(move |session: Session| {
+            let controller = account_controller.clone();
+            async move { controller.get_own_profile(session).await }
+        })
+}
+
+pub fn update_own_profile_route(
+    account_controller: Arc<AccountController>,
+    auth_controller: Arc<AuthenticationController>,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    warp::put()
+        .and(path("account"))
+        .and(path("me"))
+        .and(path::end())
+        .and(auth_controller.require_scope(Scope::AccountWrite))
+        .and(warp::body::json())
+        .and_then(move |session: Session, profile: ProfileUpdate| {
+            let controller = account_controller.clone();
+            async move { controller.update_own_profile(session, profile).await }
+        })
+}
+
+pub fn export_account_route(
+    account_controller: Arc<AccountController>,
+    auth_controller: Arc<AuthenticationController>,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    warp::get()
+        .and(path("account"))
+        .and(path("export"))
+        .and(path::end())
+        .and(auth_controller.require_scope(Scope::AccountRead))
+        .and_then(move |session: Session| {
+            let controller = account_controller.clone();
+            async move { controller.export_account(session).await }
+        })
+}
+
+pub fn get_public_profile_route(
+    account_controller: Arc<AccountController>,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    warp::get()
+        .and(path("users"))
+        .and(path::param::<i32>())
+        .and(path::end())
+        .and_then(move |id| {
+            let controller = account_controller.clone();
+            async move { controller.get_public_profile(AccountId(id)).await }
+        })
+}
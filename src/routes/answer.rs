@@ -1,19 +1,54 @@
 use std::sync::Arc;
 
-use warp::{body::json, path, Filter, Rejection, Reply};
+use warp::{body::json, path, query, Filter, Rejection, Reply};
 
 use crate::{
     controllers::{answer::AnswerController, authentication::AuthenticationController},
-    models::{account::Session, answer::{AnswerDTO, AnswerId}},
+    models::{
+        account::Session,
+        answer::{AnswerDTO, AnswerId},
+        question::QuestionId,
+        scope::Scope,
+        vote::VoteDTO,
+    },
 };
 
+pub fn get_answer_route(
+    answer_controller: Arc<AnswerController>,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    warp::get()
+        .and(path("answer"))
+        .and(path::param::<i32>())
+        .and(path::end())
+        .and_then(move |id| {
+            let controller = answer_controller.clone();
+            async move { controller.get_answer(AnswerId(id)).await }
+        })
+}
+
+pub fn get_answers_route(
+    answer_controller: Arc<AnswerController>,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    warp::get()
+        .and(path("question"))
+        .and(path::param::<i32>())
+        .and(path("answers"))
+        .and(path::end())
+        .and(query())
+        .and_then(move |id, params| {
+            let controller = answer_controller.clone();
+            async move { controller.get_answers(QuestionId(id), params).await }
+        })
+}
+
 pub fn create_answer_route(
     answer_controller: Arc<AnswerController>,
+    auth_controller: Arc<AuthenticationController>,
 ) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
     warp::post()
         .and(path("answer"))
         .and(path::end())
-        .and(AuthenticationController::auth())
+        .and(auth_controller.require_scope(Scope::AnswersWrite))
         .and(json())
         .and_then(move |session: Session, answer: AnswerDTO| {
             let controller = answer_controller.clone();
@@ -23,15 +58,64 @@ pub fn create_answer_route(
 
 pub fn update_answer_route(
     answer_controller: Arc<AnswerController>,
+    auth_controller: Arc<AuthenticationController>,
 ) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
     warp::put()
         .and(path("answer"))
         .and(path::param::<i32>())
         .and(path::end())
-        .and(AuthenticationController::auth())
+        .and(auth_controller.require_scope(Scope::AnswersWrite))
         .and(json())
         .and_then(move |id, session: Session, answer: AnswerDTO| {
             let controller = answer_controller.clone();
             async move { controller.update_answer(session, AnswerId(id), answer).await }
         })
 }
+
+pub fn delete_answer_route(
+    answer_controller: Arc<AnswerController>,
+    auth_controller: Arc<AuthenticationController>,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    warp::delete()
+        .and(path("answer"))
+        .and(path::param::<i32>())
+        .and(path::end())
+        .and(auth_controller.require_scope(Scope::AnswersWrite))
+        .and_then(move |id, session: Session| {
+            let controller = answer_controller.clone();
+            async move { controller.delete_answer(session, AnswerId(id)).await }
+        })
+}
+
+pub fn vote_answer_route(
+    answer_controller: Arc<AnswerController>,
+    auth_controller: Arc<AuthenticationController>,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    warp::post()
+        .and(path("answer"))
+        .and(path::param::<i32>())
+        .and(path("vote"))
+        .and(path::end())
+        .and(auth_controller.require_scope(Scope::AnswersWrite))
+        .and(json())
+        .and_then(move |id, session: Session, vote: VoteDTO| {
+            let controller = answer_controller.clone();
+            async move { controller.vote_answer(session, AnswerId(id), Some(vote.vote)).await }
+        })
+}
+
+pub fn unvote_answer_route(
+    answer_controller: Arc<AnswerController>,
+    auth_controller: Arc<AuthenticationController>,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    warp::delete()
+        .and(path("answer"))
+        .and(path::param::<i32>())
+        .and(path("vote"))
+        .and(path::end())
+        .and(auth_controller.require_scope(Scope::AnswersWrite))
+        .and_then(move |id, session: Session| {
+            let controller = answer_controller.clone();
+            async move { controller.vote_answer(session, AnswerId(id), None).await }
+        })
+}
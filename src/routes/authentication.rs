@@ -2,7 +2,21 @@ use std::sync::Arc;
 
 use warp::{path, Filter, Rejection, Reply};
 
-use crate::{controllers::authentication::AuthenticationController, models::account::Account};
+use crate::{
+    controllers::authentication::{AuthenticationController, OIDC_STATE_COOKIE},
+    models::{
+        api_key::ApiKeyRequest,
+        oidc::OidcCallback,
+        account::{
+            AccountDeletionRequest, EmailVerification, LoginRequest, PasswordChange,
+            PasswordResetConfirmation, PasswordResetRequest, RegistrationRequest, Session,
+            VerificationResendRequest,
+        },
+        scope::Scope,
+        token::{RefreshRequest, ScopedTokenRequest},
+        two_factor::{ChallengeResponse, TwoFactorCode},
+    },
+};
 
 pub fn login_route(
     auth_controller: Arc<AuthenticationController>,
@@ -11,9 +25,55 @@ pub fn login_route(
         .and(path("login"))
         .and(path::end())
         .and(warp::body::json())
-        .and_then(move |account: Account| {
+        .and(auth_controller.client_info())
+        .and_then(move |login: LoginRequest, client| {
             let controller = auth_controller.clone();
-            async move { controller.login(account).await }
+            async move { controller.login(login, client).await }
+        })
+}
+
+pub fn two_factor_login_route(
+    auth_controller: Arc<AuthenticationController>,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    warp::post()
+        .and(path("login"))
+        .and(path("2fa"))
+        .and(path::end())
+        .and(warp::body::json())
+        .and(auth_controller.client_info())
+        .and_then(move |response: ChallengeResponse, client| {
+            let controller = auth_controller.clone();
+            async move { controller.complete_two_factor_login(response, client).await }
+        })
+}
+
+pub fn oidc_login_route(
+    auth_controller: Arc<AuthenticationController>,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    warp::get()
+        .and(path("login"))
+        .and(path("oidc"))
+        .and(path::end())
+        .and_then(move || {
+            let controller = auth_controller.clone();
+            async move { controller.start_oidc_login().await }
+        })
+}
+
+pub fn oidc_callback_route(
+    auth_controller: Arc<AuthenticationController>,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    warp::get()
+        .and(path("login"))
+        .and(path("oidc"))
+        .and(path("callback"))
+        .and(path::end())
+        .and(warp::query())
+        .and(warp::cookie::optional::<String>(OIDC_STATE_COOKIE))
+        .and(auth_controller.client_info())
+        .and_then(move |callback: OidcCallback, state_cookie, client| {
+            let controller = auth_controller.clone();
+            async move { controller.complete_oidc_login(callback, state_cookie, client).await }
         })
 }
 
@@ -24,8 +84,270 @@ pub fn registration_route(
         .and(path("registration"))
         .and(path::end())
         .and(warp::body::json())
-        .and_then(move |account: Account| {
+        .and_then(move |registration: RegistrationRequest| {
+            let controller = auth_controller.clone();
+            async move { controller.register_account(registration).await }
+        })
+}
+
+
+pub fn verify_email_route(
+    auth_controller: Arc<AuthenticationController>,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    warp::get()
+        .and(path("registration"))
+        .and(path("verify"))
+        .and(path::end())
+        .and(warp::query())
+        .and_then(move |verification: EmailVerification| {
+            let controller = auth_controller.clone();
+            async move { controller.verify_email(verification).await }
+        })
+}
+
+pub fn resend_verification_route(
+    auth_controller: Arc<AuthenticationController>,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    warp::post()
+        .and(path("registration"))
+        .and(path("resend"))
+        .and(path::end())
+        .and(warp::body::json())
+        .and_then(move |request: VerificationResendRequest| {
+            let controller = auth_controller.clone();
+            async move { controller.resend_verification(request).await }
+        })
+}
+
+pub fn refresh_token_route(
+    auth_controller: Arc<AuthenticationController>,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    warp::post()
+        .and(path("token"))
+        .and(path("refresh"))
+        .and(path::end())
+        .and(warp::body::json())
+        .and_then(move |request: RefreshRequest| {
+            let controller = auth_controller.clone();
+            async move { controller.refresh_token(request).await }
+        })
+}
+
+pub fn scoped_token_route(
+    auth_controller: Arc<AuthenticationController>,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    warp::post()
+        .and(path("token"))
+        .and(path("scoped"))
+        .and(path::end())
+        .and(auth_controller.auth())
+        .and(warp::body::json())
+        .and(auth_controller.client_info())
+        .and_then(move |session: Session, request: ScopedTokenRequest, client| {
+            let controller = auth_controller.clone();
+            async move { controller.scoped_token(session, request, client).await }
+        })
+}
+
+pub fn logout_route(
+    auth_controller: Arc<AuthenticationController>,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    warp::post()
+        .and(path("logout"))
+        .and(path::end())
+        .and(auth_controller.auth())
+        .and_then(move |session: Session| {
+            let controller = auth_controller.clone();
+            async move { controller.logout(session).await }
+        })
+}
+
+pub fn change_password_route(
+    auth_controller: Arc<AuthenticationController>,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    warp::put()
+        .and(path("account"))
+        .and(path("password"))
+        .and(path::end())
+        .and(auth_controller.require_scope(Scope::AccountWrite))
+        .and(warp::body::json())
+        .and_then(move |session: Session, change: PasswordChange| {
+            let controller = auth_controller.clone();
+            async move { controller.change_password(session, change).await }
+        })
+}
+
+pub fn delete_account_route(
+    auth_controller: Arc<AuthenticationController>,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    warp::delete()
+        .and(path("account"))
+        .and(path::end())
+        .and(auth_controller.require_scope(Scope::AccountWrite))
+        .and(warp::body::json())
+        .and_then(move |session: Session, deletion: AccountDeletionRequest| {
+            let controller = auth_controller.clone();
+            async move { controller.delete_account(session, deletion).await }
+        })
+}
+
+pub fn password_reset_route(
+    auth_controller: Arc<AuthenticationController>,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    warp::post()
+        .and(path("password-reset"))
+        .and(path::end())
+        .and(warp::body::json())
+        .and_then(move |request: PasswordResetRequest| {
+            let controller = auth_controller.clone();
+            async move { controller.request_password_reset(request).await }
+        })
+}
+
+pub fn password_reset_confirm_route(
+    auth_controller: Arc<AuthenticationController>,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    warp::post()
+        .and(path("password-reset"))
+        .and(path("confirm"))
+        .and(path::end())
+        .and(warp::body::json())
+        .and_then(move |confirmation: PasswordResetConfirmation| {
+            let controller = auth_controller.clone();
+            async move { controller.confirm_password_reset(confirmation).await }
+        })
+}
+
+pub fn enroll_two_factor_route(
+    auth_controller: Arc<AuthenticationController>,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    warp::post()
+        .and(path("account"))
+        .and(path("2fa"))
+        .and(path::end())
+        .and(auth_controller.require_scope(Scope::AccountWrite))
+        .and_then(move |session: Session| {
+            let controller = auth_controller.clone();
+            async move { controller.enroll_two_factor(session).await }
+        })
+}
+
+pub fn confirm_two_factor_route(
+    auth_controller: Arc<AuthenticationController>,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    warp::post()
+        .and(path("account"))
+        .and(path("2fa"))
+        .and(path("confirm"))
+        .and(path::end())
+        .and(auth_controller.require_scope(Scope::AccountWrite))
+        .and(warp::body::json())
+        .and(auth_controller.client_info())
+        .and_then(move |session: Session, confirmation: TwoFactorCode, client| {
+            let controller = auth_controller.clone();
+            async move { controller.confirm_two_factor(session, confirmation, client).await }
+        })
+}
+
+pub fn disable_two_factor_route(
+    auth_controller: Arc<AuthenticationController>,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    warp::delete()
+        .and(path("account"))
+        .and(path("2fa"))
+        .and(path::end())
+        .and(auth_controller.require_scope(Scope::AccountWrite))
+        .and(warp::body::json())
+        .and(auth_controller.client_info())
+        .and_then(move |session: Session, confirmation: TwoFactorCode, client| {
+            let controller = auth_controller.clone();
+            async move { controller.disable_two_factor(session, confirmation, client).await }
+        })
+}
+
+pub fn create_api_key_route(
+    auth_controller: Arc<AuthenticationController>,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    warp::post()
+        .and(path("account"))
+        .and(path("api-keys"))
+        .and(path::end())
+        .and(auth_controller.require_scope(Scope::AccountWrite))
+        .and(warp::body::json())
+        .and_then(move |session: Session, request: ApiKeyRequest| {
+            let controller = auth_controller.clone();
+            async move { controller.create_api_key(session, request).await }
+        })
+}
+
+pub fn get_api_keys_route(
+    auth_controller: Arc<AuthenticationController>,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    warp::get()
+        .and(path("account"))
+        .and(path("api-keys"))
+        .and(path::end())
+        .and(auth_controller.require_scope(Scope::AccountRead))
+        .and_then(move |session: Session| {
+            let controller = auth_controller.clone();
+            async move { controller.get_api_keys(session).await }
+        })
+}
+
+pub fn revoke_api_key_route(
+    auth_controller: Arc<AuthenticationController>,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    warp::delete()
+        .and(path("account"))
+        .and(path("api-keys"))
+        .and(path::param::<i32>())
+        .and(path::end())
+        .and(auth_controller.require_scope(Scope::AccountWrite))
+        .and_then(move |api_key_id: i32, session: Session| {
+            let controller = auth_controller.clone();
+            async move { controller.revoke_api_key(session, api_key_id).await }
+        })
+}
+
+pub fn get_sessions_route(
+    auth_controller: Arc<AuthenticationController>,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    warp::get()
+        .and(path("account"))
+        .and(path("sessions"))
+        .and(path::end())
+        .and(auth_controller.require_scope(Scope::AccountRead))
+        .and_then(move |session: Session| {
+            let controller = auth_controller.clone();
+            async move { controller.get_sessions(session).await }
+        })
+}
+
+pub fn delete_session_route(
+    auth_controller: Arc<AuthenticationController>,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    warp::delete()
+        .and(path("account"))
+        .and(path("sessions"))
+        .and(path::param::<i32>())
+        .and(path::end())
+        .and(auth_controller.require_scope(Scope::AccountWrite))
+        .and_then(move |session_id: i32, session: Session| {
+            let controller = auth_controller.clone();
+            async move { controller.delete_session(session, session_id).await }
+        })
+}
+
+// public keys verifying session tokens in public mode, empty otherwise
+pub fn paseto_keys_route(
+    auth_controller: Arc<AuthenticationController>,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    warp::get()
+        .and(path(".well-known"))
+        .and(path("paseto-keys"))
+        .and(path::end())
+        .and_then(move || {
             let controller = auth_controller.clone();
-            async move { controller.register_account(account).await }
+            async move { controller.paseto_keys().await }
         })
 }
@@ -0,0 +1,88 @@
+use std::sync::Arc;
+
+use warp::{body::json, path, Filter, Rejection, Reply};
+
+use crate::{
+    controllers::{authentication::AuthenticationController, comment::CommentController},
+    models::{
+        account::Session,
+        answer::AnswerId,
+        comment::{CommentDTO, CommentId},
+        post::PostId,
+        question::QuestionId,
+        scope::Scope,
+    },
+};
+
+pub fn create_question_comment_route(
+    comment_controller: Arc<CommentController>,
+    auth_controller: Arc<AuthenticationController>,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    warp::post()
+        .and(path("question"))
+        .and(path::param::<i32>())
+        .and(path("comments"))
+        .and(path::end())
+        .and(auth_controller.require_scope(Scope::CommentsWrite))
+        .and(json())
+        .and_then(move |id, session: Session, comment: CommentDTO| {
+            let controller = comment_controller.clone();
+            async move {
+                controller
+                    .create_comment(session, PostId::Question(QuestionId(id)), comment)
+                    .await
+            }
+        })
+}
+
+pub fn create_answer_comment_route(
+    comment_controller: Arc<CommentController>,
+    auth_controller: Arc<AuthenticationController>,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    warp::post()
+        .and(path("answer"))
+        .and(path::param::<i32>())
+        .and(path("comments"))
+        .and(path::end())
+        .and(auth_controller.require_scope(Scope::CommentsWrite))
+        .and(json())
+        .and_then(move |id, session: Session, comment: CommentDTO| {
+            let controller = comment_controller.clone();
+            async move {
+                controller
+                    .create_comment(session, PostId::Answer(AnswerId(id)), comment)
+                    .await
+            }
+        })
+}
+
+pub fn update_comment_route(
+    comment_controller: Arc<CommentController>,
+    auth_controller: Arc<AuthenticationController>,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    warp::put()
+        .and(path("comment"))
+        .and(path::param::<i32>())
+        .and(path::end())
+        .and(auth_controller.require_scope(Scope::CommentsWrite))
+        .and(json())
+        .and_then(move |id, session: Session, comment: CommentDTO| {
+            let controller = comment_controller.clone();
+            async move { controller.update_comment(session, CommentId(id), comment).await }
+        })
+}
+
+pub fn delete_comment_route(
+    comment_controller: Arc<CommentController>,
+    auth_controller: Arc<AuthenticationController>,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    warp::delete()
+        .and(path("comment"))
+        .and(path::param::<i32>())
+        .and(path::end())
+        .and(auth_controller.require_scope(Scope::CommentsWrite))
+        .and_then(move |id, session: Session| {
+            let controller = comment_controller.clone();
+            async move { controller.delete_comment(session, CommentId(id)).await }
+        })
+}
@@ -1,3 +1,5 @@
+pub mod account;
 pub mod authentication;
 pub mod question;
-pub mod answer;
\ No newline at end of file
+pub mod answer;
+pub mod comment;
\ No newline at end of file
@@ -4,16 +4,23 @@ use warp::{path, Filter, Rejection, Reply, query};
 
 use crate::{
     controllers::{authentication::AuthenticationController, question::QuestionController},
-    models::{account::Session, question::{QuestionDTO, QuestionId}},
+    models::{
+        account::Session,
+        answer::AnswerId,
+        question::{QuestionDTO, QuestionId},
+        scope::Scope,
+        vote::VoteDTO,
+    },
 };
 
 pub fn add_question_route(
     question_controller: Arc<QuestionController>,
+    auth_controller: Arc<AuthenticationController>,
 ) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
     warp::post()
         .and(path("question"))
         .and(path::end())
-        .and(AuthenticationController::auth())
+        .and(auth_controller.require_scope(Scope::QuestionsWrite))
         .and(warp::body::json())
         .and_then(move |session: Session, new_question: QuestionDTO| {
             let controller = question_controller.clone();
@@ -28,9 +35,10 @@ pub fn get_question_route(
         .and(path("question"))
         .and(path::param::<i32>())
         .and(path::end())
-        .and_then(move |id| {
+        .and(query())
+        .and_then(move |id, params| {
             let controller = question_controller.clone();
-            async move { controller.get_question(id).await }
+            async move { controller.get_question(id, params).await }
         })
 }
 
@@ -49,12 +57,13 @@ pub fn get_questions_route(
 
 pub fn update_question_route(
     question_controller: Arc<QuestionController>,
+    auth_controller: Arc<AuthenticationController>,
 ) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
     warp::put()
         .and(path("question"))
         .and(path::param::<i32>())
         .and(path::end())
-        .and(AuthenticationController::auth())
+        .and(auth_controller.require_scope(Scope::QuestionsWrite))
         .and(warp::body::json())
         .and_then(move |id, session: Session, question: QuestionDTO| {
             let controller = question_controller.clone();
@@ -64,14 +73,85 @@ pub fn update_question_route(
 
 pub fn delete_question_route(
     question_controller: Arc<QuestionController>,
+    auth_controller: Arc<AuthenticationController>,
 ) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
     warp::delete()
         .and(path("question"))
         .and(path::param::<i32>())
         .and(path::end())
-        .and(AuthenticationController::auth())
+        .and(auth_controller.require_scope(Scope::QuestionsWrite))
         .and_then(move |id, session: Session| {
             let controller = question_controller.clone();
             async move { controller.delete_question(session, QuestionId(id)).await }
         })
 }
+
+pub fn accept_answer_route(
+    question_controller: Arc<QuestionController>,
+    auth_controller: Arc<AuthenticationController>,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    warp::post()
+        .and(path("question"))
+        .and(path::param::<i32>())
+        .and(path("accept"))
+        .and(path::param::<i32>())
+        .and(path::end())
+        .and(auth_controller.require_scope(Scope::QuestionsWrite))
+        .and_then(move |id, answer_id, session: Session| {
+            let controller = question_controller.clone();
+            async move {
+                controller
+                    .accept_answer(session, QuestionId(id), Some(AnswerId(answer_id)))
+                    .await
+            }
+        })
+}
+
+pub fn unaccept_answer_route(
+    question_controller: Arc<QuestionController>,
+    auth_controller: Arc<AuthenticationController>,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    warp::delete()
+        .and(path("question"))
+        .and(path::param::<i32>())
+        .and(path("accept"))
+        .and(path::end())
+        .and(auth_controller.require_scope(Scope::QuestionsWrite))
+        .and_then(move |id, session: Session| {
+            let controller = question_controller.clone();
+            async move { controller.accept_answer(session, QuestionId(id), None).await }
+        })
+}
+
+pub fn vote_question_route(
+    question_controller: Arc<QuestionController>,
+    auth_controller: Arc<AuthenticationController>,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    warp::post()
+        .and(path("question"))
+        .and(path::param::<i32>())
+        .and(path("vote"))
+        .and(path::end())
+        .and(auth_controller.require_scope(Scope::QuestionsWrite))
+        .and(warp::body::json())
+        .and_then(move |id, session: Session, vote: VoteDTO| {
+            let controller = question_controller.clone();
+            async move { controller.vote_question(session, QuestionId(id), Some(vote.vote)).await }
+        })
+}
+
+pub fn unvote_question_route(
+    question_controller: Arc<QuestionController>,
+    auth_controller: Arc<AuthenticationController>,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    warp::delete()
+        .and(path("question"))
+        .and(path::param::<i32>())
+        .and(path("vote"))
+        .and(path::end())
+        .and(auth_controller.require_scope(Scope::QuestionsWrite))
+        .and_then(move |id, session: Session| {
+            let controller = question_controller.clone();
+            async move { controller.vote_question(session, QuestionId(id), None).await }
+        })
+}
@@ -0,0 +1,110 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+
+use crate::config::Config;
+
+const PURGE_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug)]
+struct Attempts {
+    failures: u32,
+    last_failure: DateTime<Utc>,
+    locked_until: Option<DateTime<Utc>>,
+}
+
+// failed login attempts, keyed by account email and by client ip. After
+// `max_attempts` failures the key is locked out, doubling the lockout with
+// every further failure up to `max_lockout`. Kept in memory: a restart
+// forgets the counters, which only gives an attacker a few more guesses.
+#[derive(Debug)]
+pub struct LoginThrottle {
+    max_attempts: u32,
+    base_lockout: chrono::Duration,
+    max_lockout: chrono::Duration,
+    attempts: Mutex<HashMap<String, Attempts>>,
+}
+
+impl LoginThrottle {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            max_attempts: config.login_max_attempts,
+            base_lockout: chrono::Duration::seconds(config.login_lockout_seconds),
+            max_lockout: chrono::Duration::seconds(config.login_lockout_max_seconds),
+            attempts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn account_key(email: &str) -> String {
+        format!("account:{}", email.trim().to_lowercase())
+    }
+
+    pub fn ip_key(ip: &std::net::IpAddr) -> String {
+        format!("ip:{}", ip)
+    }
+
+    // seconds until every key is allowed to try again, None when none is locked.
+    pub fn retry_after(&self, keys: &[String]) -> Option<u64> {
+        let now = Utc::now();
+        let attempts = self.attempts.lock().unwrap();
+
+        keys.iter()
+            .filter_map(|key| attempts.get(key)?.locked_until)
+            .filter(|locked_until| *locked_until > now)
+            .map(|locked_until| (locked_until - now).num_seconds().max(1) as u64)
+            .max()
+    }
+
+    pub fn record_failure(&self, keys: &[String]) {
+        let now = Utc::now();
+        let mut attempts = self.attempts.lock().unwrap();
+
+        for key in keys {
+            let entry = attempts.entry(key.clone()).or_insert(Attempts {
+                failures: 0,
+                last_failure: now,
+                locked_until: None,
+            });
+
+            // a long enough quiet period wipes the slate
+            if now - entry.last_failure > self.max_lockout {
+                entry.failures = 0;
+            }
+
+            entry.failures += 1;
+            entry.last_failure = now;
+
+            if entry.failures >= self.max_attempts {
+                let exponent = (entry.failures - self.max_attempts).min(16);
+                let lockout = (self.base_lockout * 2_i32.pow(exponent)).min(self.max_lockout);
+                entry.locked_until = Some(now + lockout);
+            }
+        }
+    }
+
+    pub fn clear(&self, key: &str) {
+        self.attempts.lock().unwrap().remove(key);
+    }
+
+    pub fn purge(&self) {
+        let now = Utc::now();
+        self.attempts.lock().unwrap().retain(|_, entry| {
+            now - entry.last_failure <= self.max_lockout
+                || entry.locked_until.is_some_and(|locked_until| locked_until > now)
+        });
+    }
+
+    pub fn spawn_purge(throttle: Arc<Self>) {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(PURGE_INTERVAL);
+            loop {
+                interval.tick().await;
+                throttle.purge();
+            }
+        });
+    }
+}